@@ -0,0 +1,264 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::error::Error;
+use std::fmt;
+
+use crate::{Color, RenderOptions, Texture};
+
+/// Bidimensional grid of pixels that make the final image.
+/// The central pixel is located at position (x: 0, y: 0).
+/// Pixels keep their full floating point colors until the canvas is quantized into an image.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+// [-C/2, C/2)
+impl Canvas {
+    /// Creates a new Canvas with the specified resolution.
+    pub fn new(width: u32, height: u32) -> Canvas {
+        let pixels = vec![Color::new(0.0, 0.0, 0.0); (width * height) as usize];
+
+        Canvas{width, height, pixels}
+    }
+
+    /// Updates the pixel at position (x, y) of the canvas.
+    pub fn put_pixel(&mut self, x: i32, y: i32, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    /// The color of the pixel at position (x, y) of the canvas.
+    pub fn get_pixel(&self, x: i32, y: i32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    /// The width of this canvas.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of this canvas.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Exposure adjustment, in stops, that brings the log-average luminance of the canvas to `key`.
+    /// The log-average is dominated by the typical brightness of the image
+    /// rather than by a few very bright or very dark pixels.
+    pub fn auto_exposure(&self, key: f64) -> f64 {
+        // Keeps black pixels from sending the logarithm to -infinity.
+        const DELTA: f64 = 1e-4;
+
+        let log_sum: f64 = self.pixels.iter().map(|pixel| (DELTA + pixel.luminance().max(0.0)).ln()).sum();
+        let log_average = (log_sum / self.pixels.len().max(1) as f64).exp();
+
+        (key / log_average).log2()
+    }
+
+    /// Quantizes the canvas into an image with 8 bits per channel.
+    /// The options control how colors are brought into range and whether they are dithered.
+    pub fn to_rgb8(&self, options: &RenderOptions) -> RgbImage {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let offset = if options.dither { dither_offset(x, y) } else { 0.0 };
+            to_rgb(&self.output_color(x, y, options), offset)
+        })
+    }
+
+    /// Quantizes the canvas into an image with 16 bits per channel.
+    /// The options control how colors are brought into range and whether they are dithered.
+    pub fn to_rgb16(&self, options: &RenderOptions) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let offset = if options.dither { dither_offset(x, y) } else { 0.0 };
+            to_rgb16(&self.output_color(x, y, options), offset)
+        })
+    }
+
+    /// Quantizes the canvas into a caller-owned buffer of 8-bit pixels, in rows from top to bottom.
+    /// Fails if the buffer doesn't exactly fit the canvas in the specified format.
+    pub fn write_into(&self, buffer: &mut [u8], format: PixelFormat, options: &RenderOptions) -> Result<(), BufferSizeError> {
+        self.check_buffer_size(buffer, format)?;
+
+        let channels = format.channels();
+        for (i, pixel) in buffer.chunks_exact_mut(channels).enumerate() {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            let offset = if options.dither { dither_offset(x, y) } else { 0.0 };
+            let rgb = to_rgb(&self.output_color(x, y, options), offset);
+
+            pixel[..3].copy_from_slice(&rgb.0);
+            if format == PixelFormat::Rgba {
+                pixel[3] = 255;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the buffer exactly fits the canvas in the specified format.
+    pub fn check_buffer_size(&self, buffer: &[u8], format: PixelFormat) -> Result<(), BufferSizeError> {
+        let expected = (self.width * self.height) as usize * format.channels();
+
+        if buffer.len() == expected {
+            Ok(())
+        } else {
+            Err(BufferSizeError{expected, actual: buffer.len()})
+        }
+    }
+
+    /// The final color of the pixel at position (x, y) of the image, before quantization.
+    fn output_color(&self, x: u32, y: u32, options: &RenderOptions) -> Color {
+        let color = self.pixels[(y * self.width + x) as usize] * 2f64.powf(options.exposure);
+
+        if options.preserve_hue { color.clamp_preserving_hue() } else { color }
+    }
+
+    /// Index in `pixels` of the pixel at position (x, y) of the canvas.
+    fn index(&self, x: i32, y: i32) -> usize {
+        let x = (self.width as i32)/2 + x;
+        let y = (self.height as i32)/2 - (y + 1);
+        (y as u32 * self.width + x as u32) as usize
+    }
+}
+
+/// Quantizes the color to 8 bits per channel.
+/// `offset`, in [0, 1), is added to each channel before truncation and is used for dithering.
+fn to_rgb(color: &Color, offset: f64) -> Rgb<u8> {
+    let quantize = |c: f64| (c * 255.0 + offset).min(255.0) as u8;
+
+    Rgb([quantize(color.r), quantize(color.g), quantize(color.b)])
+}
+
+/// Quantizes the color to 16 bits per channel.
+/// `offset`, in [0, 1), is added to each channel before truncation and is used for dithering.
+fn to_rgb16(color: &Color, offset: f64) -> Rgb<u16> {
+    let quantize = |c: f64| (c * 65535.0 + offset).min(65535.0) as u16;
+
+    Rgb([quantize(color.r), quantize(color.g), quantize(color.b)])
+}
+
+impl From<Rgb<u8>> for Color {
+    fn from(rgb: Rgb<u8>) -> Color {
+        Color::new(
+            rgb[0] as f64 / 255.0,
+            rgb[1] as f64 / 255.0,
+            rgb[2] as f64 / 255.0,
+        )
+    }
+}
+
+impl From<&RgbImage> for Texture {
+    fn from(image: &RgbImage) -> Texture {
+        Texture::from_fn(image.width(), image.height(), |x, y| Color::from(*image.get_pixel(x, y)))
+    }
+}
+
+/// Layout of each pixel in a buffer of 8-bit channels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFormat {
+    Rgb,
+
+    /// Rgb followed by an alpha channel, which is always opaque.
+    Rgba,
+}
+
+impl PixelFormat {
+    /// Number of channels, and bytes, per pixel.
+    pub fn channels(&self) -> usize {
+        match self {
+            PixelFormat::Rgb => 3,
+            PixelFormat::Rgba => 4,
+        }
+    }
+}
+
+/// A pixel buffer that doesn't match the size of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BufferSizeError {
+    /// Length, in bytes, that the buffer should have.
+    pub expected: usize,
+
+    /// Length, in bytes, of the buffer.
+    pub actual: usize,
+}
+
+impl fmt::Display for BufferSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pixel buffer has {} bytes, expected {}", self.actual, self.expected)
+    }
+}
+
+impl Error for BufferSizeError {}
+
+// 8x8 Bayer matrix, tiled over the canvas to produce the dither thresholds.
+const DITHER_MATRIX: [[u8; 8]; 8] = [
+    [ 0, 32,  8, 40,  2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44,  4, 36, 14, 46,  6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [ 3, 35, 11, 43,  1, 33,  9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47,  7, 39, 13, 45,  5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// The dither offset, in [0, 1), for the pixel at position (x, y) of the image.
+fn dither_offset(x: u32, y: u32) -> f64 {
+    let threshold = DITHER_MATRIX[(y % 8) as usize][(x % 8) as usize];
+
+    (threshold as f64 + 0.5) / 64.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dithering_breaks_up_banding() {
+        // A gradient spanning less than one 8-bit step, from 100 to 100.9 out of 255.
+        let mut canvas = Canvas::new(64, 8);
+        for x in -32..32 {
+            let value = (100.0 + 0.9 * (x + 32) as f64 / 64.0) / 255.0;
+            for y in -4..4 {
+                canvas.put_pixel(x, y, Color::new(value, value, value));
+            }
+        }
+        let dither = RenderOptions::builder().dither(true).build().unwrap();
+        let changes = |options: &RenderOptions| {
+            let image = canvas.to_rgb8(options);
+            let row: Vec<u8> = image.pixels().take(64).map(|pixel| pixel[0]).collect();
+            row.windows(2).filter(|pair| pair[0] != pair[1]).count()
+        };
+
+        // Without dithering the whole gradient collapses into a single band.
+        assert_eq!(changes(&RenderOptions::default()), 0);
+        assert!(changes(&dither) > 16);
+
+        // The dithered values still average out to the gradient.
+        let dithered = canvas.to_rgb8(&dither);
+        let average = dithered.pixels().map(|pixel| pixel[0] as f64).sum::<f64>() / (64.0 * 8.0);
+        assert!((average - 100.45).abs() < 0.05, "{}", average);
+    }
+
+    #[test]
+    fn sixteen_bits_keep_more_of_a_gradient() {
+        // A subtle gradient, like a clear sky, across 1000 pixels.
+        let mut canvas = Canvas::new(1000, 2);
+        for x in -500..500 {
+            let value = 0.4 + 0.05 * (x + 500) as f64 / 1000.0;
+            for y in -1..1 {
+                canvas.put_pixel(x, y, Color::new(value, value, value));
+            }
+        }
+        let options = RenderOptions::default();
+
+        // Distinct values along the first row.
+        let mut eight: Vec<u8> = canvas.to_rgb8(&options).pixels().take(1000).map(|pixel| pixel[0]).collect();
+        let mut sixteen: Vec<u16> = canvas.to_rgb16(&options).pixels().take(1000).map(|pixel| pixel[0]).collect();
+        eight.dedup();
+        sixteen.dedup();
+
+        // 0.05 spans about 13 steps at 8 bits, and more steps than pixels at 16 bits.
+        assert!(eight.len() <= 14);
+        assert_eq!(sixteen.len(), 1000);
+    }
+}
@@ -1,199 +1,400 @@
-use image::{RgbImage, Rgb};
-use nalgebra::{Vector3};
-use std::f64::INFINITY;
-
-/// Contains all objects and lights to be rendered.
-pub struct Scene {
-    pub objects: Vec<Sphere>,
-    pub lights: Vec<Light>,
+use nalgebra::{Rotation3, Vector3};
+use std::panic::{self, AssertUnwindSafe};
+
+mod canvas;
+pub mod render_core;
+mod options;
+mod resize;
+
+pub use crate::canvas::{BufferSizeError, Canvas, PixelFormat};
+pub use crate::render_core::{Aabb, Background, Color, ImageFit, Light, LightKind, Material, Ray, Sampler, Scene, SceneWarning, ScreenPoint, ShadingOptions, SkyModel, Sphere, Texture};
+use crate::render_core::{PreparedLight, trace_albedo, trace_ray, trace_uv};
+pub use crate::resize::resize_linear;
+pub use crate::options::{BitDepth, RenderMode, RenderOptions, RenderOptionsBuilder, RenderOptionsError};
+
+/// Rectangle in front of the camera onto which the canvas is projected.
+/// Rays are cast from the camera through points on the viewport.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: f64,
+    pub height: f64,
+    pub distance: f64, // Distance from the camera
+
+    /// Offset (x, y) of the viewport's center from the view axis, for tilt-shift projections.
+    /// Shifting instead of turning the camera keeps vertical lines vertical.
+    pub shift: (f64, f64),
+
+    /// Rotation of the viewport plane, in radians, about its horizontal and vertical axes.
+    /// A positive x tilt moves the top edge away from the camera,
+    /// a positive y tilt moves the right edge towards it.
+    pub tilt: (f64, f64),
 }
 
-/// A 3d spherical primitive.
-pub struct Sphere {
-    pub center: Vector3<f64>,
-    pub radius: u32,
-    pub color: Rgb<u8>,
-    pub shininess: Option<i32>,
-}
-
-/// Type of the light.
-pub enum LightKind {
-    /// Ambient light that illuminates all points in the scene.
-    Ambient,
-    
-    /// A point of light located in a specified position.
-    /// Sends rays of light in all directions.
-    Point(Vector3<f64>),
-
-    /// Various light rays hitting the objects in the specified direction.
-    Directional(Vector3<f64>),
-}
-
-/// Light that illuminates the objects in the scene.
-pub struct Light {
-    pub kind: LightKind,
-    pub intensity: f64,
-}
+impl Viewport {
+    /// Creates a new Viewport with the specified dimensions and distance from the camera,
+    /// centered on the view axis and facing the camera.
+    pub fn new(width: f64, height: f64, distance: f64) -> Viewport {
+        Viewport{width, height, distance, shift: (0.0, 0.0), tilt: (0.0, 0.0)}
+    }
 
-/// Bidimensional grid of pixels that make the final image.
-/// The central pixel is located at position (x: 0, y: 0).
-pub struct Canvas {
-    image: RgbImage,
+    /// The point of the viewport that the pixel at position (x, y) of the canvas is projected onto,
+    /// relative to the camera.
+    pub fn canvas_to_viewport(&self, x: i32, y: i32, canvas: &Canvas) -> Vector3<f64> {
+        let offset = Vector3::new(
+            x as f64 * self.width / canvas.width() as f64,
+            y as f64 * self.height / canvas.height() as f64,
+            0.0
+        );
+        let tilt = Rotation3::from_euler_angles(self.tilt.0, self.tilt.1, 0.0);
+        let center = Vector3::new(self.shift.0, self.shift.1, self.distance);
+
+        center + tilt * offset
+    }
 }
 
-// [-C/2, C/2)
-impl Canvas {
-    /// Creates a new Canvas with the specified resolution.
-    pub fn new(width: u32, height: u32) -> Canvas {
-        Canvas{image: RgbImage::new(width, height)}
-    }
-    /// Updates the pixel at position (x, y) of the canvas.
-    pub fn put_pixel(&mut self, x: i32, y: i32, pixel: Rgb<u8>) {
-        let x = (self.image.width() as i32)/2 + x;
-        let y = (self.image.height() as i32)/2 - (y + 1);
-        self.image.put_pixel(x as u32, y as u32, pixel);
+/// Renders the scene and saves it to the output.png file.
+/// With an exposure bracket, saves one output_<EV>ev.png file per exposure offset instead,
+/// e.g. output_-2ev.png, all from the same render.
+pub fn render(canvas: &mut Canvas, scene: &Scene, options: &RenderOptions) {
+    render_canvas(canvas, scene, options);
+    let options = &auto_exposed(canvas, options);
+
+    if options.exposure_bracket.is_empty() {
+        save_canvas(canvas, options, "output.png");
     }
 
-    /// The width of this canvas.
-    pub fn width(&self) -> u32 {
-        self.image.width()
+    for offset in &options.exposure_bracket {
+        let bracket_options = RenderOptions {
+            exposure: options.exposure + offset,
+            ..options.clone()
+        };
+        save_canvas(canvas, &bracket_options, &format!("output_{:+}ev.png", offset));
     }
+}
 
-    /// The height of this canvas.
-    pub fn height(&self) -> u32 {
-        self.image.height()
+/// The options with the auto exposure adjustment for the rendered canvas added to the exposure.
+/// Returns the options unchanged if auto exposure is disabled.
+fn auto_exposed(canvas: &Canvas, options: &RenderOptions) -> RenderOptions {
+    match options.auto_exposure {
+        Some(key) => RenderOptions {
+            exposure: options.exposure + canvas.auto_exposure(key),
+            ..options.clone()
+        },
+        None => options.clone(),
     }
 }
 
-struct Viewport {
-    width: u32,
-    height: u32,
-    distance: f64, // Distance from the camera
+fn save_canvas(canvas: &Canvas, options: &RenderOptions, path: &str) {
+    match options.output_bit_depth {
+        BitDepth::Eight => canvas.to_rgb8(options).save(path).unwrap(),
+        BitDepth::Sixteen => canvas.to_rgb16(options).save(path).unwrap(),
+    }
 }
 
-trait Scalable {
-    fn scale(&self, scalar: f64) -> Self;
+/// Renders the scene into a caller-owned buffer of 8-bit pixels, in rows from top to bottom,
+/// without touching the filesystem. The canvas provides the resolution and keeps the full colors.
+/// Fails without rendering if the buffer doesn't exactly fit the canvas in the specified format.
+pub fn render_into(
+    canvas: &mut Canvas, scene: &Scene, options: &RenderOptions,
+    buffer: &mut [u8], format: PixelFormat
+) -> Result<(), BufferSizeError> {
+    canvas.check_buffer_size(buffer, format)?;
+    render_canvas(canvas, scene, options);
+    canvas.write_into(buffer, format, &auto_exposed(canvas, options))
 }
 
-// Create own color type, then convert inside put_pixel ??
-impl Scalable for Rgb<u8> {
-    fn scale(&self, scalar: f64) -> Rgb<u8> {
-        let mut new_color = [0u8; 3];
-
-        for i in 0..3 {
-            new_color[i] = (self[i] as f64 * scalar).min(255.0) as u8;
-        }
-
-        Rgb(new_color)
-    }
-}
+/// Renders the scene into the canvas, without saving it.
+pub fn render_canvas(canvas: &mut Canvas, scene: &Scene, options: &RenderOptions) {
+    let lights = scene.prepare_lights();
 
-/// Renders the scene and saves it to the output.png file.
-pub fn render(canvas: &mut Canvas, scene: &Scene) {
-    let origin = Vector3::new(0.0, 0.0, 0.0);
-    let viewport = Viewport {
-        width: 1,
-        height: 1,
-        distance: 1.0
-    };
-   
     //for x, y, _  in canvas.enumerate_pixels()
     let cw = canvas.width() as i32;
     let ch = canvas.height() as i32;
     for x in -cw/2..cw/2 {
         for y in -ch/2..ch/2 {
-            let direction = canvas_to_viewport(x, y, &canvas, &viewport);
-            let color = trace_ray(&scene, &origin, &direction, 1.0, INFINITY);
+            let color = if options.catch_panics {
+                let pixel = panic::catch_unwind(AssertUnwindSafe(|| trace_pixel(canvas, scene, &lights, options, x, y)));
+                pixel.unwrap_or_else(|_| {
+                    eprintln!("panicked while rendering pixel ({}, {})", x, y);
+                    Color::new(1.0, 0.0, 1.0)
+                })
+            } else {
+                trace_pixel(canvas, scene, &lights, options, x, y)
+            };
             canvas.put_pixel(x, y, color);
         }
     }
+}
+
+/// Computes the color of the pixel at position (x, y) of the canvas,
+/// without rendering the rest of the image.
+/// Useful for debugging a single pixel.
+pub fn render_pixel(canvas: &Canvas, scene: &Scene, options: &RenderOptions, x: i32, y: i32) -> Color {
+    trace_pixel(canvas, scene, &scene.prepare_lights(), options, x, y)
+}
+
+/// Computes the colors of the row y of the canvas, from left to right.
+pub fn render_scanline(canvas: &Canvas, scene: &Scene, options: &RenderOptions, y: i32) -> Vec<Color> {
+    let lights = scene.prepare_lights();
+    let cw = canvas.width() as i32;
 
-    canvas.image.save("output.png").unwrap();
+    (-cw/2..cw/2).map(|x| trace_pixel(canvas, scene, &lights, options, x, y)).collect()
 }
 
-fn canvas_to_viewport(x: i32, y: i32, canvas: &Canvas, viewport: &Viewport) -> Vector3<f64> {
-    Vector3::new(
-        x as f64 * viewport.width as f64 / canvas.width() as f64,
-        y as f64 * viewport.height as f64 / canvas.height() as f64,
-        viewport.distance
-    )
+/// Computes the color of the pixel at position (x, y) of the canvas, with the scene's lights already prepared.
+fn trace_pixel(canvas: &Canvas, scene: &Scene, lights: &[PreparedLight], options: &RenderOptions, x: i32, y: i32) -> Color {
+    let origin = Vector3::new(0.0, 0.0, 0.0);
+    let viewport = &options.viewport;
+
+    let ray = Ray::new(origin, viewport.canvas_to_viewport(x, y, canvas));
+    let t_min = ray.t_at_distance(options.near_clip);
+
+    // The center of the pixel, measured from the top left corner of the canvas.
+    // Uses the same integer offsets as `Canvas::index`, so odd sizes land on the pixel that is written.
+    let (cw, ch) = (canvas.width() as i32, canvas.height() as i32);
+    let screen = ScreenPoint {
+        x: (cw/2 + x) as f64 + 0.5,
+        y: (ch/2 - (y + 1)) as f64 + 0.5,
+        width: cw as f64,
+        height: ch as f64,
+    };
+
+    match options.mode {
+        RenderMode::Beauty => trace_ray(scene, lights, &ray, &screen, t_min, f64::INFINITY, &options.shading),
+        RenderMode::Albedo => trace_albedo(scene, &ray, &screen, t_min, f64::INFINITY),
+        RenderMode::Uv => trace_uv(scene, &ray, &screen, t_min, f64::INFINITY),
+    }
 }
 
-fn trace_ray(scene: &Scene, origin: &Vector3<f64>, direction: &Vector3<f64>, t_min: f64, t_max: f64) -> Rgb<u8> {
-    let mut closest_t = INFINITY;
-    let mut closest_sphere = None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untilted_viewport_is_the_pinhole_projection() {
+        let canvas = Canvas::new(200, 100);
+        let viewport = Viewport::new(2.0, 1.5, 1.25);
+
+        for &(x, y) in &[(0, 0), (-100, -50), (99, 49), (37, -12)] {
+            let expected = Vector3::new(x as f64 * 2.0 / 200.0, y as f64 * 1.5 / 100.0, 1.25);
+            assert_eq!(viewport.canvas_to_viewport(x, y, &canvas), expected);
+        }
+    }
+
+    #[test]
+    fn shift_moves_the_center_ray() {
+        let canvas = Canvas::new(100, 100);
+        let viewport = Viewport{shift: (0.3, -0.2), ..Viewport::new(1.0, 1.0, 1.0)};
+
+        assert_eq!(viewport.canvas_to_viewport(0, 0, &canvas), Vector3::new(0.3, -0.2, 1.0));
+        assert_eq!(
+            viewport.canvas_to_viewport(20, 40, &canvas) - viewport.canvas_to_viewport(0, 0, &canvas),
+            Viewport::new(1.0, 1.0, 1.0).canvas_to_viewport(20, 40, &canvas) - Vector3::new(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn tilt_turns_the_viewport_edges() {
+        let canvas = Canvas::new(100, 100);
+
+        let tilted = Viewport{tilt: (0.2, 0.0), ..Viewport::new(1.0, 1.0, 1.0)};
+        assert!(tilted.canvas_to_viewport(0, 49, &canvas).z > 1.0);
+        assert!(tilted.canvas_to_viewport(0, -50, &canvas).z < 1.0);
+        assert_eq!(tilted.canvas_to_viewport(0, 0, &canvas).z, 1.0);
+
+        let turned = Viewport{tilt: (0.0, 0.2), ..Viewport::new(1.0, 1.0, 1.0)};
+        assert!(turned.canvas_to_viewport(49, 0, &canvas).z < 1.0);
+        assert!(turned.canvas_to_viewport(-50, 0, &canvas).z > 1.0);
+    }
+
+    #[test]
+    fn near_clip_is_the_same_distance_at_the_center_and_corner() {
+        let canvas = Canvas::new(100, 100);
+        let viewport = Viewport::new(1.0, 1.0, 1.0);
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        let center = Ray::new(origin, viewport.canvas_to_viewport(0, 0, &canvas));
+        let corner = Ray::new(origin, viewport.canvas_to_viewport(-50, -50, &canvas));
+
+        let near_clip = 0.25;
+        let clip_distance = |ray: &Ray| ray.at(ray.t_at_distance(near_clip)).norm();
+        assert!((clip_distance(&center) - near_clip).abs() < 1e-12);
+        assert!((clip_distance(&corner) - near_clip).abs() < 1e-12);
+
+        // A fixed t would clip the corner further away than the center.
+        let t = center.t_at_distance(near_clip);
+        assert!(corner.at(t).norm() > center.at(t).norm() * 1.2);
+    }
+
+    #[test]
+    fn sphere_between_the_camera_and_viewport_is_visible() {
+        let canvas = Canvas::new(10, 10);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let scene = Scene {
+            objects: vec![Sphere{center: Vector3::new(0.0, 0.0, 0.5), radius: 0.2, material: None}],
+            lights: vec![Light{kind: LightKind::Ambient, intensity: 1.0}],
+            background: Background::Color(black),
+            default_material: Material::gray(),
+        };
+
+        let near = RenderOptions::builder().near_clip(0.01).build().unwrap();
+        assert_eq!(render_pixel(&canvas, &scene, &near, 0, 0), Material::gray().color);
 
-    for primitive in &scene.objects {
-        let (t1, t2) = intersect_ray_sphere(&origin, &direction, &primitive);
+        // Clipping at the viewport hides the whole sphere.
+        let viewport = RenderOptions::builder().near_clip(1.0).build().unwrap();
+        assert_eq!(render_pixel(&canvas, &scene, &viewport, 0, 0), black);
+    }
 
-        for t in [t1, t2] {
-            if (t > t_min && t < t_max) && t < closest_t {
-                closest_t = t;
-                closest_sphere = Some(primitive);
+    #[test]
+    fn saturating_light_keeps_its_hue() {
+        let orange = Material{color: Color::new(1.0, 0.5, 0.25), ..Material::default()};
+        let scene = Scene {
+            objects: vec![Sphere{center: Vector3::new(0.0, 0.0, 3.0), radius: 2.0, material: Some(orange)}],
+            lights: vec![Light{kind: LightKind::Ambient, intensity: 4.0}],
+            background: Background::Color(Color::new(0.0, 0.0, 0.0)),
+            default_material: Material::gray(),
+        };
+        let mut canvas = Canvas::new(2, 2);
+        render_canvas(&mut canvas, &scene, &RenderOptions::default());
+
+        // The canvas keeps the unclamped light, four times the material color.
+        assert_eq!(canvas.get_pixel(0, 0), Color::new(4.0, 2.0, 1.0));
+
+        let clamped = canvas.to_rgb8(&RenderOptions::default());
+        let preserved = canvas.to_rgb8(&RenderOptions::builder().preserve_hue(true).build().unwrap());
+        for y in 0..2 {
+            for x in 0..2 {
+                assert_eq!(clamped.get_pixel(x, y).0, [255, 255, 255]);
+                assert_eq!(preserved.get_pixel(x, y).0, [255, 127, 63]);
             }
         }
     }
 
-    match closest_sphere {
-        Some(sphere) => {
-            let point = origin + direction.scale(closest_t);
-            let normal = (point - sphere.center).normalize();
-            sphere.color.scale(compute_lighting(&scene, &point, &normal, &(-direction), sphere.shininess))
+    /// A scene showing a 2x2 screen image with a red top row and a blue bottom row.
+    fn striped_scene() -> Scene {
+        let rows = [Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0)];
+        Scene {
+            objects: vec![],
+            lights: vec![],
+            background: Background::ScreenImage(Texture::from_fn(2, 2, |_, y| rows[y as usize]), ImageFit::Stretch),
+            default_material: Material::gray(),
         }
-        None => Rgb([255, 255, 255])
     }
-}
 
+    #[test]
+    fn mismatched_buffers_are_rejected_untouched() {
+        let mut canvas = Canvas::new(2, 2);
 
-fn intersect_ray_sphere(origin: &Vector3<f64>, direction: &Vector3<f64>, sphere: &Sphere) -> (f64, f64) {
-    let r = sphere.radius;
-    let co = origin - sphere.center;
-      
-    let a = direction.dot(&direction);
-    let b = 2.0 * co.dot(&direction);
-    let c = co.dot(&co) - (r*r) as f64;
+        for &(len, format) in &[(11, PixelFormat::Rgb), (13, PixelFormat::Rgb), (12, PixelFormat::Rgba), (17, PixelFormat::Rgba)] {
+            let mut buffer = vec![7; len];
+            let expected = 4 * format.channels();
 
-    let discriminant = b*b - 4.0*a*c;
-    if discriminant < 0.0 {
-        return (INFINITY, INFINITY);
+            let result = render_into(&mut canvas, &striped_scene(), &RenderOptions::default(), &mut buffer, format);
+            assert_eq!(result, Err(BufferSizeError{expected, actual: len}));
+            assert!(buffer.iter().all(|&byte| byte == 7));
+        }
     }
 
-    let t1 = (-b + discriminant.sqrt()) / (2.0*a);
-    let t2 = (-b - discriminant.sqrt()) / (2.0*a);
+    #[test]
+    fn rgba_buffers_are_opaque_rows_from_the_top() {
+        let mut canvas = Canvas::new(2, 2);
+        let mut buffer = vec![0; 16];
+        render_into(&mut canvas, &striped_scene(), &RenderOptions::default(), &mut buffer, PixelFormat::Rgba).unwrap();
 
-    (t1, t2)
-}
+        assert_eq!(buffer, [
+            255, 0, 0, 255,  255, 0, 0, 255,
+            0, 0, 255, 255,  0, 0, 255, 255,
+        ]);
+    }
 
-fn compute_lighting(
-    scene: &Scene, point: &Vector3<f64>, normal: &Vector3<f64>,
-    view: &Vector3<f64>, shininess: Option<i32>
-) -> f64 {
-    let mut illumination = 0.0;
-
-    for light in &scene.lights {
-        let point_to_light: Vector3<f64>;
-
-        match light.kind {
-            LightKind::Ambient => {
-                illumination += light.intensity;
-                continue;
-            },
-            LightKind::Point(light_position) => point_to_light = (light_position - point).normalize(),
-            LightKind::Directional(direction) => point_to_light = direction.normalize(),
-        };
+    /// A scene that panics while rendering the bottom right pixel of a 2x2 canvas, and shows green elsewhere.
+    fn panicking_scene() -> Scene {
+        Scene {
+            objects: vec![],
+            lights: vec![],
+            background: Background::ScreenImage(Texture::missing_last_pixel(2, 2), ImageFit::Stretch),
+            default_material: Material::gray(),
+        }
+    }
+
+    #[test]
+    fn caught_panics_fill_the_pixel_with_magenta() {
+        let mut canvas = Canvas::new(2, 2);
+        let options = RenderOptions::builder().catch_panics(true).build().unwrap();
+        render_canvas(&mut canvas, &panicking_scene(), &options);
+
+        assert_eq!(canvas.get_pixel(0, -1), Color::new(1.0, 0.0, 1.0));
+        for &(x, y) in &[(-1, 0), (0, 0), (-1, -1)] {
+            assert_eq!(canvas.get_pixel(x, y), Color::new(0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn uncaught_panics_abort_the_render() {
+        let options = RenderOptions::builder().catch_panics(false).build().unwrap();
+        render_canvas(&mut Canvas::new(2, 2), &panicking_scene(), &options);
+    }
 
-        // difuse
-        illumination += light.intensity * normal.dot(&point_to_light).max(0.0);
+    #[test]
+    fn screen_image_misses_show_the_backdrop_pixel() {
+        // Odd sizes put the center between pixels differently on each axis.
+        for &(width, height) in &[(40, 30), (41, 31), (41, 30)] {
+            let backdrop = Texture::from_fn(width, height, |x, y| Color::new(x as f64 / 41.0, y as f64 / 31.0, 0.5));
+            let canvas = Canvas::new(width, height);
+            let (cw, ch) = (width as i32, height as i32);
+
+            for fit in [ImageFit::Stretch, ImageFit::Fit, ImageFit::Fill] {
+                let scene = Scene {
+                    objects: vec![],
+                    lights: vec![],
+                    background: Background::ScreenImage(backdrop.clone(), fit),
+                    default_material: Material::gray(),
+                };
+
+                for x in -cw/2..cw/2 {
+                    for y in -ch/2..ch/2 {
+                        // Canvas y goes up from the center, image rows go down from the top.
+                        let expected = backdrop.get_pixel((cw/2 + x) as u32, (ch/2 - (y + 1)) as u32);
+                        let color = render_pixel(&canvas, &scene, &RenderOptions::default(), x, y);
+                        assert_eq!(color, expected, "{}x{} {:?} at ({}, {})", width, height, fit, x, y);
+                    }
+                }
+            }
+        }
+    }
 
-        // specular
-        if let Some(shininess) = shininess {
-            let reflection = normal.scale(2.0*normal.dot(&point_to_light)) - point_to_light;
-            let view = view.normalize();
 
-            illumination += light.intensity * reflection.dot(&view).max(0.0).powi(shininess);
+    #[test]
+    fn auto_exposure_brings_dark_and_bright_scenes_to_the_target() {
+        let key = 0.18;
+        let options = RenderOptions::builder().auto_exposure(Some(key)).build().unwrap();
+
+        for (intensity, background) in [(0.05, 0.02), (3.0, 0.9)] {
+            let scene = Scene {
+                objects: vec![Sphere{center: Vector3::new(0.0, 0.0, 3.0), radius: 1.0, material: None}],
+                lights: vec![Light{kind: LightKind::Point(Vector3::new(2.0, 2.0, 0.0)), intensity}],
+                background: Background::Color(Color::new(background, background, background)),
+                default_material: Material::default(),
+            };
+            let mut canvas = Canvas::new(64, 64);
+            let mut buffer = vec![0; 64 * 64 * 3];
+            render_into(&mut canvas, &scene, &options, &mut buffer, PixelFormat::Rgb).unwrap();
+
+            // Log-average of the exposed colors, before and after quantization.
+            let scale = 2f64.powf(canvas.auto_exposure(key));
+            let log_average = |luminances: Vec<f64>| {
+                let count = luminances.len() as f64;
+                (luminances.into_iter().map(|luminance| (1e-4 + luminance).ln()).sum::<f64>() / count).exp()
+            };
+            let exposed = (-32..32).flat_map(|x| (-32..32).map(move |y| (x, y)))
+                .map(|(x, y)| canvas.get_pixel(x, y).luminance() * scale)
+                .collect();
+            let quantized = buffer.chunks_exact(3).map(|pixel| pixel[0] as f64 / 255.0).collect();
+
+            // Black pixels don't scale with the exposure, so they pull the dark scene a bit under the target.
+            assert!((log_average(exposed) / key - 1.0).abs() < 0.2);
+            assert!((log_average(quantized) / key - 1.0).abs() < 0.2);
         }
     }
 
-    illumination
 }
-
@@ -1,33 +1,53 @@
-use raytracer::{Canvas, Scene, Sphere, Light, LightKind};
+use raytracer::{Canvas, Scene, Sphere, Material, Light, LightKind, Background, Color, RenderOptions};
 use nalgebra::{Vector3};
-use image::Rgb;
 
 fn main() {
     let mut canvas = Canvas::new(1024, 1024);
 
+    raytracer::render(&mut canvas, &scene(), &RenderOptions::default());
+}
+
+/// The scene rendered into output.png.
+fn scene() -> Scene {
     let s1 = Sphere {
         center: Vector3::new(0.0, -1.0, 3.0),
-        radius: 1,
-        color: Rgb([255, 0, 0]),
-        shininess: Some(500),
+        radius: 1.0,
+        material: Some(Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            specular_color: Color::new(1.0, 0.0, 0.0),
+            shininess: Some(500),
+            ..Material::default()
+        }),
     };
     let s2 = Sphere {
         center: Vector3::new(2.0, 0.0, 4.0),
-        radius: 1,
-        color: Rgb([0, 0, 255]),
-        shininess: Some(500),
+        radius: 1.0,
+        material: Some(Material {
+            color: Color::new(0.0, 0.0, 1.0),
+            specular_color: Color::new(0.0, 0.0, 1.0),
+            shininess: Some(500),
+            ..Material::default()
+        }),
     };
     let s3 = Sphere {
         center: Vector3::new(-2.0, 0.0, 4.0),
-        radius: 1,
-        color: Rgb([0, 255, 0]),
-        shininess: Some(10),
+        radius: 1.0,
+        material: Some(Material {
+            color: Color::new(0.0, 1.0, 0.0),
+            specular_color: Color::new(0.0, 1.0, 0.0),
+            shininess: Some(10),
+            ..Material::default()
+        }),
     };
     let s4 = Sphere {
         center: Vector3::new(0.0, -5001.0, 0.0),
-        radius: 5000,
-        color: Rgb([255, 255, 0]),
-        shininess: Some(1000),
+        radius: 5000.0,
+        material: Some(Material {
+            color: Color::new(1.0, 1.0, 0.0),
+            specular_color: Color::new(1.0, 1.0, 0.0),
+            shininess: Some(1000),
+            ..Material::default()
+        }),
     };
 
     let l1 = Light {
@@ -44,10 +64,36 @@ fn main() {
     };
 
 
-    let scene = Scene {
+    Scene {
         objects: vec![s1, s2, s3, s4],
         lights: vec![l1, l2, l3],
-    };
-    
-    raytracer::render(&mut canvas, &scene);
+        background: Background::Color(Color::new(1.0, 1.0, 1.0)),
+        default_material: Material::gray(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scene_matches_output_png() {
+        let expected = image::open(concat!(env!("CARGO_MANIFEST_DIR"), "/output.png")).unwrap().to_rgb8();
+        let (width, height) = expected.dimensions();
+        let (scene, options) = (scene(), RenderOptions::default());
+
+        // Rendering every pixel is slow without optimizations, every 4th pixel in each direction is enough.
+        let mut canvas = Canvas::new(width, height);
+        let (cw, ch) = (width as i32, height as i32);
+        let pixels: Vec<(i32, i32)> = (-cw/2..cw/2).step_by(4).flat_map(|x| (-ch/2..ch/2).step_by(4).map(move |y| (x, y))).collect();
+        for &(x, y) in &pixels {
+            canvas.put_pixel(x, y, raytracer::render_pixel(&canvas, &scene, &options, x, y));
+        }
+
+        let rendered = canvas.to_rgb8(&options);
+        for (x, y) in pixels {
+            let (column, row) = ((x + cw/2) as u32, (ch/2 - 1 - y) as u32);
+            assert_eq!(rendered.get_pixel(column, row), expected.get_pixel(column, row), "pixel ({}, {})", x, y);
+        }
+    }
 }
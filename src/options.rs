@@ -0,0 +1,316 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{ShadingOptions, Viewport};
+
+/// Settings that control how the scene is rendered.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Applies an ordered dither before quantizing colors,
+    /// breaking up the banding in smooth gradients.
+    pub dither: bool,
+
+    /// Bits per channel of the output image.
+    pub output_bit_depth: BitDepth,
+
+    /// Exposure adjustment, in stops, applied to the colors before they are output.
+    /// Every +1 doubles the brightness.
+    pub exposure: f64,
+
+    /// Target log-average luminance of the image, or `None` to disable auto exposure.
+    /// When set, the exposure is adjusted after rendering so the image's log-average luminance
+    /// matches the target, 0.18 being middle gray. `exposure` then acts as exposure compensation
+    /// on top of the automatic adjustment.
+    pub auto_exposure: Option<f64>,
+
+    /// Exposure offsets, in stops, to output the render at, on top of `exposure`.
+    /// Each one is saved as a separate image, from the same render.
+    /// When empty, a single image is saved.
+    pub exposure_bracket: Vec<f64>,
+
+    /// Brings overexposed colors into range by scaling them down as a whole instead of
+    /// clamping each channel, so bright areas keep their hue instead of washing out.
+    /// Colors stay unbounded through all of the lighting until this final step.
+    pub preserve_hue: bool,
+
+    /// Distance from the camera below which objects are clipped.
+    /// Objects closer to the camera than the near clip are not rendered.
+    pub near_clip: f64,
+
+    /// The viewport primary rays are cast through.
+    pub viewport: Viewport,
+
+    /// Recovers from panics while rendering a pixel instead of aborting the whole render.
+    /// The pixel is filled with magenta and its position is logged to stderr.
+    pub catch_panics: bool,
+
+    /// What is computed for each pixel.
+    pub mode: RenderMode,
+
+    /// How surfaces are shaded.
+    pub shading: ShadingOptions,
+}
+
+/// Number of bits per channel of an image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+/// What the renderer outputs for each pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderMode {
+    /// The fully shaded image.
+    Beauty,
+
+    /// The base color of the surfaces, without any lighting.
+    Albedo,
+
+    /// Debug pass showing the surface texture coordinates, u as red and v as green.
+    Uv,
+}
+
+impl RenderOptions {
+    /// Creates a builder starting from the default options.
+    pub fn builder() -> RenderOptionsBuilder {
+        RenderOptionsBuilder{options: RenderOptions::default()}
+    }
+}
+
+/// The defaults reproduce the original behavior of `render`: no dithering, 8-bit output with
+/// no exposure adjustment or auto exposure and channels clamped separately, a 1x1 viewport at distance 1
+/// and panics propagating. The exception is the near clip, `render` used to clip everything closer than the viewport,
+/// which `near_clip: 1.0` approximates.
+/// Rendering always takes one sample per pixel and saves a PNG; the background is part of the Scene.
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            dither: false,
+            output_bit_depth: BitDepth::Eight,
+            exposure: 0.0,
+            auto_exposure: None,
+            exposure_bracket: Vec::new(),
+            preserve_hue: false,
+            near_clip: 0.001,
+            viewport: Viewport::new(1.0, 1.0, 1.0),
+            catch_panics: false,
+            mode: RenderMode::Beauty,
+            shading: ShadingOptions::default(),
+        }
+    }
+}
+
+/// Builds RenderOptions with chainable setters, validating them at the end.
+pub struct RenderOptionsBuilder {
+    options: RenderOptions,
+}
+
+impl RenderOptionsBuilder {
+    /// Sets whether colors are dithered before being quantized.
+    pub fn dither(mut self, dither: bool) -> RenderOptionsBuilder {
+        self.options.dither = dither;
+        self
+    }
+
+    /// Sets the bits per channel of the output image.
+    pub fn output_bit_depth(mut self, output_bit_depth: BitDepth) -> RenderOptionsBuilder {
+        self.options.output_bit_depth = output_bit_depth;
+        self
+    }
+
+    /// Sets the exposure adjustment, in stops.
+    pub fn exposure(mut self, exposure: f64) -> RenderOptionsBuilder {
+        self.options.exposure = exposure;
+        self
+    }
+
+    /// Sets the target log-average luminance for auto exposure, or `None` to disable it.
+    pub fn auto_exposure(mut self, auto_exposure: Option<f64>) -> RenderOptionsBuilder {
+        self.options.auto_exposure = auto_exposure;
+        self
+    }
+
+    /// Sets the exposure offsets, in stops, to save the render at.
+    pub fn exposure_bracket(mut self, exposure_bracket: Vec<f64>) -> RenderOptionsBuilder {
+        self.options.exposure_bracket = exposure_bracket;
+        self
+    }
+
+    /// Sets whether overexposed colors keep their hue.
+    pub fn preserve_hue(mut self, preserve_hue: bool) -> RenderOptionsBuilder {
+        self.options.preserve_hue = preserve_hue;
+        self
+    }
+
+    /// Sets the distance from the camera below which objects are clipped.
+    pub fn near_clip(mut self, near_clip: f64) -> RenderOptionsBuilder {
+        self.options.near_clip = near_clip;
+        self
+    }
+
+    /// Sets the viewport primary rays are cast through.
+    pub fn viewport(mut self, viewport: Viewport) -> RenderOptionsBuilder {
+        self.options.viewport = viewport;
+        self
+    }
+
+    /// Sets whether panics while rendering a pixel are recovered from.
+    pub fn catch_panics(mut self, catch_panics: bool) -> RenderOptionsBuilder {
+        self.options.catch_panics = catch_panics;
+        self
+    }
+
+    /// Sets what is computed for each pixel.
+    pub fn mode(mut self, mode: RenderMode) -> RenderOptionsBuilder {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Sets whether emissive objects illuminate other surfaces.
+    pub fn emissive_lighting(mut self, emissive_lighting: bool) -> RenderOptionsBuilder {
+        self.options.shading.emissive_lighting = emissive_lighting;
+        self
+    }
+
+    /// Sets the number of rays used to estimate the ambient occlusion of each point, 0 to disable it.
+    pub fn ambient_occlusion_samples(mut self, ambient_occlusion_samples: u32) -> RenderOptionsBuilder {
+        self.options.shading.ambient_occlusion_samples = ambient_occlusion_samples;
+        self
+    }
+
+    /// Sets the distance from a surface below which the rays cast from it ignore hits.
+    pub fn surface_epsilon(mut self, surface_epsilon: f64) -> RenderOptionsBuilder {
+        self.options.shading.surface_epsilon = surface_epsilon;
+        self
+    }
+
+    /// Validates the options and returns them.
+    pub fn build(self) -> Result<RenderOptions, RenderOptionsError> {
+        let options = self.options;
+
+        if !(options.near_clip >= 0.0 && options.near_clip.is_finite()) {
+            return Err(RenderOptionsError::InvalidNearClip(options.near_clip));
+        }
+
+        let surface_epsilon = options.shading.surface_epsilon;
+        if !(surface_epsilon >= 0.0 && surface_epsilon.is_finite()) {
+            return Err(RenderOptionsError::InvalidSurfaceEpsilon(surface_epsilon));
+        }
+
+        let mut exposures = std::iter::once(&options.exposure).chain(&options.exposure_bracket);
+        if let Some(exposure) = exposures.find(|exposure| !exposure.is_finite()) {
+            return Err(RenderOptionsError::InvalidExposure(*exposure));
+        }
+
+        if let Some(key) = options.auto_exposure {
+            if !(key > 0.0 && key.is_finite()) {
+                return Err(RenderOptionsError::InvalidAutoExposure(key));
+            }
+        }
+
+        let viewport = &options.viewport;
+        let positive = |value: f64| value > 0.0 && value.is_finite();
+        let offsets = [viewport.shift.0, viewport.shift.1, viewport.tilt.0, viewport.tilt.1];
+        let finite_offsets = offsets.iter().all(|offset| offset.is_finite());
+        if !(positive(viewport.width) && positive(viewport.height) && positive(viewport.distance) && finite_offsets) {
+            return Err(RenderOptionsError::InvalidViewport(*viewport));
+        }
+
+        Ok(options)
+    }
+}
+
+/// Reason why RenderOptions failed to validate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderOptionsError {
+    /// The near clip is negative or not finite.
+    InvalidNearClip(f64),
+
+    /// The surface epsilon is negative or not finite.
+    InvalidSurfaceEpsilon(f64),
+
+    /// An exposure or exposure offset is not finite.
+    InvalidExposure(f64),
+
+    /// The auto exposure target is not positive and finite.
+    InvalidAutoExposure(f64),
+
+    /// The viewport has a dimension or distance that is not positive and finite,
+    /// or a shift or tilt that is not finite.
+    InvalidViewport(Viewport),
+}
+
+impl fmt::Display for RenderOptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderOptionsError::InvalidNearClip(near_clip) => write!(f, "invalid near clip {}", near_clip),
+            RenderOptionsError::InvalidSurfaceEpsilon(epsilon) => write!(f, "invalid surface epsilon {}", epsilon),
+            RenderOptionsError::InvalidExposure(exposure) => write!(f, "invalid exposure {}", exposure),
+            RenderOptionsError::InvalidAutoExposure(key) => write!(f, "invalid auto exposure target {}", key),
+            RenderOptionsError::InvalidViewport(viewport) => write!(f, "invalid viewport {:?}", viewport),
+        }
+    }
+}
+
+impl Error for RenderOptionsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport_error(viewport: Viewport) -> Result<RenderOptions, RenderOptionsError> {
+        RenderOptions::builder().viewport(viewport).build()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(RenderOptions::builder().build().is_ok());
+    }
+
+    #[test]
+    fn invalid_near_clip() {
+        let error = RenderOptions::builder().near_clip(-0.1).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidNearClip(-0.1));
+
+        let error = RenderOptions::builder().near_clip(f64::INFINITY).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidNearClip(f64::INFINITY));
+    }
+
+    #[test]
+    fn invalid_surface_epsilon() {
+        let error = RenderOptions::builder().surface_epsilon(-1.0).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidSurfaceEpsilon(-1.0));
+    }
+
+    #[test]
+    fn invalid_exposure() {
+        let error = RenderOptions::builder().exposure(f64::INFINITY).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidExposure(f64::INFINITY));
+
+        let error = RenderOptions::builder().exposure_bracket(vec![-1.0, f64::NEG_INFINITY]).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidExposure(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn invalid_auto_exposure() {
+        let error = RenderOptions::builder().auto_exposure(Some(0.0)).build().unwrap_err();
+        assert_eq!(error, RenderOptionsError::InvalidAutoExposure(0.0));
+    }
+
+    #[test]
+    fn invalid_viewport() {
+        let invalid = [
+            Viewport::new(0.0, 1.0, 1.0),
+            Viewport::new(1.0, -1.0, 1.0),
+            Viewport::new(1.0, 1.0, f64::INFINITY),
+            Viewport{shift: (f64::INFINITY, 0.0), ..Viewport::new(1.0, 1.0, 1.0)},
+            Viewport{tilt: (0.0, f64::NAN), ..Viewport::new(1.0, 1.0, 1.0)},
+        ];
+
+        for viewport in invalid {
+            // NaN viewports can't be compared, so only match the variant.
+            assert!(matches!(viewport_error(viewport), Err(RenderOptionsError::InvalidViewport(_))), "{:?}", viewport);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use nalgebra::{Vector3};
+
+use super::Ray;
+
+/// Axis-aligned bounding box, spanning from `min` to `max` on every axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f64>,
+    pub max: Vector3<f64>,
+}
+
+impl Aabb {
+    /// Creates a new Aabb with the specified corners.
+    pub fn new(min: Vector3<f64>, max: Vector3<f64>) -> Aabb {
+        Aabb{min, max}
+    }
+
+    /// A box containing nothing, which is the identity for `union`.
+    /// Its min is infinite and its max is negative infinite, so no ray hits it.
+    pub fn empty() -> Aabb {
+        let infinity = Vector3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+
+        Aabb::new(infinity, -infinity)
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.inf(&other.min), self.max.sup(&other.max))
+    }
+
+    /// Whether the ray intersects the box for some t in [t_min, t_max].
+    /// Rays that only touch an edge or a corner of the box count as hits.
+    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        // Slab test: clip the range of t to where the ray is between the two planes of each axis.
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse_direction;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse_direction;
+            if inverse_direction < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // A ray parallel to the planes that starts on one of them gives NaN,
+            // which f64::max and f64::min ignore, leaving the range untouched.
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    fn hits(origin: [f64; 3], direction: [f64; 3]) -> bool {
+        let ray = Ray::new(Vector3::from(origin), Vector3::from(direction));
+        unit_box().hit(&ray, 0.0, f64::INFINITY)
+    }
+
+    #[test]
+    fn ray_grazing_a_corner_hits() {
+        // Touches the box only at the corner (1, 1, 1).
+        assert!(hits([0.0, 0.0, 2.0], [1.0, 1.0, -1.0]));
+    }
+
+    #[test]
+    fn ray_passing_by_misses() {
+        assert!(!hits([0.1, 0.1, 2.0], [1.0, 1.0, -1.0]));
+        assert!(!hits([2.0, 2.0, 2.0], [0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn ray_parallel_to_a_slab() {
+        // Inside the x and y slabs, outside of the x slab, and on one of the x planes.
+        assert!(hits([0.5, 0.5, -1.0], [0.0, 0.0, 1.0]));
+        assert!(!hits([2.0, 0.5, -1.0], [0.0, 0.0, 1.0]));
+        assert!(hits([1.0, 0.5, -1.0], [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn ray_with_a_negative_direction() {
+        assert!(hits([0.5, 0.5, 3.0], [0.0, 0.0, -1.0]));
+        assert!(hits([3.0, 2.0, 0.5], [-2.0, -1.5, 0.0]));
+
+        // The box is behind the origin.
+        assert!(!hits([0.5, 0.5, -1.0], [0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn hits_are_limited_to_the_range() {
+        let ray = Ray::new(Vector3::new(0.5, 0.5, -2.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!(unit_box().hit(&ray, 0.0, 2.0));
+        assert!(!unit_box().hit(&ray, 0.0, 1.5));
+        assert!(!unit_box().hit(&ray, 3.5, 10.0));
+    }
+
+    #[test]
+    fn empty_box_is_never_hit() {
+        let ray = Ray::new(Vector3::new(0.5, 0.5, -2.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!(!Aabb::empty().hit(&ray, 0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn union_contains_both_boxes() {
+        let other = Aabb::new(Vector3::new(-1.0, 0.5, 2.0), Vector3::new(0.5, 3.0, 4.0));
+
+        let union = unit_box().union(&other);
+        assert_eq!(union, Aabb::new(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 3.0, 4.0)));
+        assert_eq!(other.union(&unit_box()), union);
+    }
+
+    #[test]
+    fn union_with_the_empty_box_is_the_identity() {
+        assert_eq!(unit_box().union(&Aabb::empty()), unit_box());
+        assert_eq!(Aabb::empty().union(&unit_box()), unit_box());
+        assert_eq!(Aabb::empty().union(&Aabb::empty()), Aabb::empty());
+    }
+}
@@ -0,0 +1,200 @@
+use nalgebra::{Vector3};
+use std::f64::consts::PI;
+
+use super::{Color, SkyModel, Texture};
+
+/// What is seen along rays that don't hit any object.
+pub enum Background {
+    /// The same color in every direction.
+    Color(Color),
+
+    /// An environment made of six square faces, in the order +x, -x, +y, -y, +z, -z.
+    /// Faces follow the usual cube map layout, as seen from inside the cube:
+    /// the four side faces are upright (+y up) and wrap around the camera.
+    /// Empty faces are seen as black.
+    CubeMap([Texture; 6]),
+
+    /// A procedural daylight sky.
+    Sky(SkyModel),
+
+    /// An image fixed to the frame, independent of the direction of the rays,
+    /// like a photo backdrop behind the scene. An empty image is seen as black.
+    ScreenImage(Texture, ImageFit),
+}
+
+/// How a screen image is fitted to a frame with a different aspect ratio.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFit {
+    /// Scales the image to the frame, distorting it if the aspect ratios differ.
+    Stretch,
+
+    /// Scales the image to fit inside the frame, centered, leaving black bars around it.
+    Fit,
+
+    /// Scales the image to cover the frame, centered, cropping what falls outside of it.
+    Fill,
+}
+
+/// Position of a primary ray within the frame, used to look up screen images.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenPoint {
+    /// Position in pixels, from the top left corner of the frame.
+    pub x: f64,
+    pub y: f64,
+
+    /// Size of the frame in pixels.
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Background {
+    /// The color seen along `direction`, from the point `screen` of the frame.
+    pub fn sample(&self, direction: &Vector3<f64>, screen: &ScreenPoint) -> Color {
+        match self {
+            Background::Color(color) => *color,
+            Background::CubeMap(faces) => {
+                let (face, u, v) = cube_map_face(direction);
+                let image = &faces[face];
+                if image.is_empty() {
+                    return Color::new(0.0, 0.0, 0.0);
+                }
+
+                let x = ((u * image.width() as f64) as u32).min(image.width() - 1);
+                let y = ((v * image.height() as f64) as u32).min(image.height() - 1);
+                image.get_pixel(x, y)
+            }
+            Background::Sky(sky) => sky.sample(direction),
+            Background::ScreenImage(image, fit) => {
+                let (image_width, image_height) = (image.width() as f64, image.height() as f64);
+                let (scale_x, scale_y) = (screen.width / image_width, screen.height / image_height);
+                let (scale_x, scale_y) = match fit {
+                    ImageFit::Stretch => (scale_x, scale_y),
+                    ImageFit::Fit => (scale_x.min(scale_y), scale_x.min(scale_y)),
+                    ImageFit::Fill => (scale_x.max(scale_y), scale_x.max(scale_y)),
+                };
+
+                // Position in the image, keeping the centers of the image and the frame together.
+                let x = (screen.x - screen.width / 2.0) / scale_x + image_width / 2.0;
+                let y = (screen.y - screen.height / 2.0) / scale_y + image_height / 2.0;
+                if !(x >= 0.0 && x < image_width && y >= 0.0 && y < image_height) {
+                    return Color::new(0.0, 0.0, 0.0);
+                }
+
+                let x = (x as u32).min(image.width() - 1);
+                let y = (y as u32).min(image.height() - 1);
+                image.get_pixel(x, y)
+            }
+        }
+    }
+
+    /// Mirrors the background along the z axis.
+    pub(super) fn mirror_z(&mut self) {
+        match self {
+            // Screen images don't depend on the direction of the rays.
+            Background::Color(_) | Background::ScreenImage(..) => (),
+            Background::CubeMap(faces) => {
+                // The z faces trade places and every face has its z axis reversed:
+                // horizontally for the x and z faces, vertically for the y faces.
+                faces.swap(4, 5);
+                for (face, image) in faces.iter_mut().enumerate() {
+                    if face == 2 || face == 3 {
+                        image.flip_vertical();
+                    } else {
+                        image.flip_horizontal();
+                    }
+                }
+            }
+            Background::Sky(sky) => sky.sun_azimuth = PI - sky.sun_azimuth,
+        }
+    }
+}
+
+/// Selects the cube map face seen along `direction` and the position (u, v) within it,
+/// with u going right and v going down the face's image, both in [0, 1].
+fn cube_map_face(direction: &Vector3<f64>) -> (usize, f64, f64) {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+    // The major axis picks the face, the other two give the coordinates on it.
+    let (face, major, sc, tc) = if ax >= ay && ax >= az {
+        if x > 0.0 { (0, ax, -z, -y) } else { (1, ax, z, -y) }
+    } else if ay >= az {
+        if y > 0.0 { (2, ay, x, z) } else { (3, ay, x, -z) }
+    } else if z > 0.0 {
+        (4, az, x, -y)
+    } else {
+        (5, az, -x, -y)
+    };
+
+    (face, (sc / major + 1.0) / 2.0, (tc / major + 1.0) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x3 face whose center pixel is `center` and whose other pixels are black.
+    fn face(center: Color) -> Texture {
+        Texture::from_fn(3, 3, |x, y| if (x, y) == (1, 1) { center } else { Color::new(0.0, 0.0, 0.0) })
+    }
+
+    fn screen() -> ScreenPoint {
+        ScreenPoint{x: 0.5, y: 0.5, width: 1.0, height: 1.0}
+    }
+
+    #[test]
+    fn axis_directions_map_to_face_centers() {
+        let axes = [
+            Vector3::new(1.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, -1.0),
+        ];
+
+        for (expected_face, axis) in axes.iter().enumerate() {
+            assert_eq!(cube_map_face(axis), (expected_face, 0.5, 0.5));
+        }
+
+        // Sampling each axis sees the center pixel of its own face.
+        let colors = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6].map(|value| Color::new(value, 0.0, 0.0));
+        let cube_map = Background::CubeMap(colors.map(face));
+        for (axis, color) in axes.iter().zip(colors) {
+            assert_eq!(cube_map.sample(axis, &screen()), color);
+        }
+    }
+
+    #[test]
+    fn empty_faces_are_black() {
+        let empty = || Texture::from_fn(0, 0, |_, _| Color::new(1.0, 1.0, 1.0));
+        let cube_map = Background::CubeMap([empty(), empty(), empty(), empty(), empty(), empty()]);
+
+        assert_eq!(cube_map.sample(&Vector3::new(0.0, 0.0, 1.0), &screen()), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn screen_image_fit_modes() {
+        // A 4x1 image, with a different color in each column, on a square frame.
+        let columns = [Color::new(1.0, 0.0, 0.0), Color::new(0.0, 1.0, 0.0), Color::new(0.0, 0.0, 1.0), Color::new(1.0, 1.0, 1.0)];
+        let image = Texture::from_fn(4, 1, |x, _| columns[x as usize]);
+        let sample = |fit, x, y| {
+            let screen = ScreenPoint{x, y, width: 100.0, height: 100.0};
+            Background::ScreenImage(image.clone(), fit).sample(&Vector3::z(), &screen)
+        };
+        let [red, green, blue, white] = columns;
+        let black = Color::new(0.0, 0.0, 0.0);
+
+        // Stretched over the whole frame, each column takes a quarter of it.
+        assert_eq!(sample(ImageFit::Stretch, 10.0, 10.0), red);
+        assert_eq!(sample(ImageFit::Stretch, 40.0, 90.0), green);
+        assert_eq!(sample(ImageFit::Stretch, 90.0, 50.0), white);
+
+        // Fit to the width, with black bars above and below.
+        assert_eq!(sample(ImageFit::Fit, 10.0, 50.0), red);
+        assert_eq!(sample(ImageFit::Fit, 90.0, 50.0), white);
+        assert_eq!(sample(ImageFit::Fit, 50.0, 10.0), black);
+        assert_eq!(sample(ImageFit::Fit, 50.0, 90.0), black);
+
+        // Filling the height crops the outer columns, only the middle two are seen.
+        assert_eq!(sample(ImageFit::Fill, 10.0, 10.0), green);
+        assert_eq!(sample(ImageFit::Fill, 90.0, 90.0), blue);
+    }
+}
@@ -0,0 +1,63 @@
+use std::ops::{Add, AddAssign, Mul};
+
+/// A linear RGB color with floating point channels.
+/// Channels are nominally in the [0, 1] range, but are only clamped when
+/// the color is quantized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Color {
+    /// Creates a new color from its channels.
+    pub fn new(r: f64, g: f64, b: f64) -> Color {
+        Color{r, g, b}
+    }
+
+    /// Brings the color into the [0, 1] range by scaling it down by its brightest channel,
+    /// keeping the ratios between channels, and so its hue, unlike clamping each channel.
+    pub fn clamp_preserving_hue(&self) -> Color {
+        let color = Color::new(self.r.max(0.0), self.g.max(0.0), self.b.max(0.0));
+        let max = color.r.max(color.g).max(color.b);
+
+        if max > 1.0 { color * (1.0 / max) } else { color }
+    }
+
+    /// Perceived brightness of the color, with the Rec. 709 weights.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+}
+
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        Color::new(self.r * other.r, self.g * other.g, self.b * other.b)
+    }
+}
+
+impl Mul<f64> for Color {
+    type Output = Color;
+
+    fn mul(self, scalar: f64) -> Color {
+        Color::new(self.r * scalar, self.g * scalar, self.b * scalar)
+    }
+}
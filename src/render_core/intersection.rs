@@ -0,0 +1,145 @@
+use std::ops::Deref;
+
+use super::{Ray, Sphere};
+
+/// Values of t where a ray hits an object, sorted from nearest to farthest.
+/// Holds the up to two hits of a sphere without allocating, and derefs to a slice of them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hits {
+    ts: [f64; 2],
+    len: usize,
+}
+
+impl Hits {
+    fn new() -> Hits {
+        Hits{ts: [0.0; 2], len: 0}
+    }
+
+    fn push(&mut self, t: f64) {
+        self.ts[self.len] = t;
+        self.len += 1;
+    }
+}
+
+impl Deref for Hits {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.ts[..self.len]
+    }
+}
+
+impl Sphere {
+    /// The values of t in (t_min, t_max) where the ray enters or leaves the sphere,
+    /// sorted from nearest to farthest. Empty if the ray misses the sphere within the range,
+    /// and a single hit if the ray is tangent to it.
+    pub fn hits(&self, ray: &Ray, t_min: f64, t_max: f64) -> Hits {
+        let (t1, t2) = intersect_ray_sphere(ray, self);
+        let (near, far) = if t1 <= t2 { (t1, t2) } else { (t2, t1) };
+
+        let mut hits = Hits::new();
+        if near > t_min && near < t_max {
+            hits.push(near);
+        }
+        if far > t_min && far < t_max && far != near {
+            hits.push(far);
+        }
+        hits
+    }
+}
+
+/// Computes the values of t where the ray intersects the sphere.
+/// Both are `f64::INFINITY` if the ray misses it, `Sphere::hits` handles misses and ranges for you.
+pub fn intersect_ray_sphere(ray: &Ray, sphere: &Sphere) -> (f64, f64) {
+    let direction = &ray.direction;
+    let r = sphere.radius;
+    let co = ray.origin - sphere.center;
+      
+    let a = direction.dot(direction);
+    let b = 2.0 * co.dot(direction);
+    let c = co.dot(&co) - r*r;
+
+    let discriminant = b*b - 4.0*a*c;
+    if discriminant < 0.0 {
+        return (f64::INFINITY, f64::INFINITY);
+    }
+
+    let t1 = (-b + discriminant.sqrt()) / (2.0*a);
+    let t2 = (-b - discriminant.sqrt()) / (2.0*a);
+
+    (t1, t2)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::render_core::{Background, Color, Material, Sampler, Scene, closest_intersection};
+
+    fn unit_sphere() -> Sphere {
+        Sphere{center: Vector3::new(0.0, 0.0, 5.0), radius: 1.0, material: None}
+    }
+
+    fn ray_from(x: f64, y: f64, z: f64) -> Ray {
+        Ray::new(Vector3::new(x, y, z), Vector3::new(0.0, 0.0, 1.0))
+    }
+
+    #[test]
+    fn miss_has_no_hits() {
+        assert!(unit_sphere().hits(&ray_from(0.0, 2.0, 0.0), 0.0, f64::INFINITY).is_empty());
+    }
+
+    #[test]
+    fn tangent_ray_has_one_hit() {
+        assert_eq!(&*unit_sphere().hits(&ray_from(0.0, 1.0, 0.0), 0.0, f64::INFINITY), &[5.0]);
+    }
+
+    #[test]
+    fn two_hits_are_sorted() {
+        assert_eq!(&*unit_sphere().hits(&ray_from(0.0, 0.0, 0.0), 0.0, f64::INFINITY), &[4.0, 6.0]);
+    }
+
+    #[test]
+    fn hits_outside_the_range_are_dropped() {
+        let sphere = unit_sphere();
+
+        // From inside the sphere, the entry point is behind the origin.
+        assert_eq!(&*sphere.hits(&ray_from(0.0, 0.0, 5.0), 0.001, f64::INFINITY), &[1.0]);
+        assert_eq!(&*sphere.hits(&ray_from(0.0, 0.0, 0.0), 0.0, 5.0), &[4.0]);
+    }
+
+    #[test]
+    fn closest_intersection_matches_the_nearest_root() {
+        let sampler = Sampler::new(7);
+        let random = |i: u32, dimension: u32| sampler.sample(i as i32, 0, 0, dimension) * 2.0 - 1.0;
+        let objects: Vec<Sphere> = (0..10).map(|i| Sphere {
+            center: Vector3::new(random(i, 0) * 3.0, random(i, 1) * 3.0, random(i, 2) * 3.0 + 5.0),
+            radius: random(i, 3).abs() + 0.1,
+            material: None,
+        }).collect();
+        let scene = Scene {
+            objects,
+            lights: vec![],
+            background: Background::Color(Color::new(0.0, 0.0, 0.0)),
+            default_material: Material::gray(),
+        };
+
+        for i in 0..1000 {
+            let direction = Vector3::new(random(i, 4), random(i, 5), random(i, 6) + 0.5);
+            let ray = Ray::new(Vector3::new(0.0, 0.0, 0.0), direction);
+
+            // The nearest of all the roots in range, found directly from intersect_ray_sphere.
+            let expected = scene.objects.iter()
+                .flat_map(|sphere| {
+                    let (t1, t2) = intersect_ray_sphere(&ray, sphere);
+                    vec![t1, t2]
+                })
+                .filter(|&t| t > 0.001 && t < f64::INFINITY)
+                .fold(None, |closest: Option<f64>, t| Some(closest.map_or(t, |closest| closest.min(t))));
+
+            let t = closest_intersection(&scene, &ray, 0.001, f64::INFINITY).map(|(_, t)| t);
+            assert_eq!(t, expected, "ray {}", i);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use nalgebra::{Vector3};
+use std::f64::consts::PI;
+
+use super::{Color, Light, LightKind, Material, Ray, Scene};
+
+/// A light with everything that doesn't depend on the shaded point computed up front,
+/// so it isn't recomputed for every point. Built with `PreparedLight::new`.
+#[derive(Clone, Copy, Debug)]
+pub enum PreparedLight {
+    Ambient {
+        intensity: f64,
+    },
+    Point {
+        position: Vector3<f64>,
+        intensity: f64,
+    },
+    Directional {
+        /// Normalized direction towards the light.
+        direction: Vector3<f64>,
+        intensity: f64,
+    },
+    Spot {
+        position: Vector3<f64>,
+        /// Normalized direction of the cone's axis.
+        direction: Vector3<f64>,
+        cos_inner: f64,
+        cos_outer: f64,
+        intensity: f64,
+    },
+}
+
+impl PreparedLight {
+    /// Prepares a light for shading.
+    pub fn new(light: &Light) -> PreparedLight {
+        let intensity = light.intensity;
+
+        match light.kind {
+            LightKind::Ambient => PreparedLight::Ambient{intensity},
+            LightKind::Point(position) => PreparedLight::Point{position, intensity},
+            LightKind::Directional(direction) => PreparedLight::Directional{direction: direction.normalize(), intensity},
+            LightKind::Spot{position, direction, inner_angle, outer_angle} => PreparedLight::Spot {
+                position,
+                direction: direction.normalize(),
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+                intensity,
+            },
+        }
+    }
+}
+
+impl Scene {
+    /// Prepares all the lights in the scene for shading.
+    pub fn prepare_lights(&self) -> Vec<PreparedLight> {
+        self.lights.iter().map(PreparedLight::new).collect()
+    }
+}
+
+/// Settings that control how surfaces are shaded.
+#[derive(Clone, Copy, Debug)]
+pub struct ShadingOptions {
+    /// Lets emissive objects illuminate other surfaces, approximating each one as a point light
+    /// at its center whose intensity falls off with the square of the distance.
+    /// The approximation ignores the size of the object and casts no shadows,
+    /// so it only suits small glowing objects, not large emissive surfaces.
+    pub emissive_lighting: bool,
+
+    /// Number of rays cast over the hemisphere around the normal to estimate how much of the
+    /// ambient light reaches each point. Ambient light is scaled by the fraction of rays that
+    /// escape the scene, darkening creases and contact points. Rays are occluded by objects
+    /// at any distance. 0 disables ambient occlusion and lights every point uniformly.
+    pub ambient_occlusion_samples: u32,
+
+    /// Distance from a surface below which the rays cast from it ignore hits,
+    /// so the surface doesn't occlude itself because of floating point error.
+    /// It is a world-space distance, so scale it along with the scene.
+    pub surface_epsilon: f64,
+}
+
+/// The defaults disable emissive lighting and ambient occlusion, and offset rays from surfaces by 0.001.
+impl Default for ShadingOptions {
+    fn default() -> ShadingOptions {
+        ShadingOptions {
+            emissive_lighting: false,
+            ambient_occlusion_samples: 0,
+            surface_epsilon: 0.001,
+        }
+    }
+}
+
+/// Computes the color of the light reflected towards the viewer by `point`,
+/// on a surface with the specified `normal` and `material`.
+/// `view` points from the surface towards the viewer.
+/// `lights` are the scene's lights, prepared with `Scene::prepare_lights`.
+pub fn compute_lighting(
+    scene: &Scene, lights: &[PreparedLight], point: &Vector3<f64>, normal: &Vector3<f64>,
+    view: &Vector3<f64>, material: &Material, options: &ShadingOptions
+) -> Color {
+    // Diffuse illumination, including ambient, is tinted by the surface color
+    // and specular illumination by the specular color.
+    let mut illumination = 0.0;
+    let mut specular_illumination = 0.0;
+    let mut emitted_illumination = Color::new(0.0, 0.0, 0.0);
+
+    if options.emissive_lighting {
+        for sphere in &scene.objects {
+            let emission = scene.material(sphere).emission;
+            if emission == Color::new(0.0, 0.0, 0.0) {
+                continue;
+            }
+
+            // Full emission at the surface of the sphere, fading with the squared distance.
+            // Shading the emissive sphere itself adds nothing: its center is behind the surface.
+            let point_to_light = sphere.center - point;
+            let falloff = (sphere.radius * sphere.radius / point_to_light.norm_squared()).min(1.0);
+            let incidence = normal.dot(&point_to_light.normalize()).max(0.0);
+            emitted_illumination += emission * (falloff * incidence);
+        }
+    }
+
+    // Occlusion only affects ambient light, don't cast its rays if there is none.
+    let ambient_lit = lights.iter().any(|light| matches!(light, PreparedLight::Ambient{..}));
+    let ambient_visibility = match options.ambient_occlusion_samples {
+        samples if samples > 0 && ambient_lit => {
+            1.0 - ambient_occlusion(scene, point, normal, samples, options.surface_epsilon)
+        },
+        _ => 1.0,
+    };
+
+    for light in lights {
+        let (point_to_light, intensity) = match *light {
+            PreparedLight::Ambient{intensity} => {
+                illumination += intensity * material.ambient * ambient_visibility;
+                continue;
+            },
+            PreparedLight::Point{position, intensity} => ((position - point).normalize(), intensity),
+            PreparedLight::Directional{direction, intensity} => (direction, intensity),
+            PreparedLight::Spot{position, direction, cos_inner, cos_outer, intensity} => {
+                let point_to_light = (position - point).normalize();
+                let falloff = spot_falloff(&(-point_to_light), &direction, cos_inner, cos_outer);
+                (point_to_light, intensity * falloff)
+            },
+        };
+
+        // difuse
+        illumination += intensity * normal.dot(&point_to_light).max(0.0);
+
+        // specular
+        if let Some(shininess) = material.shininess {
+            let reflection = normal.scale(2.0*normal.dot(&point_to_light)) - point_to_light;
+            let view = view.normalize();
+
+            specular_illumination += intensity * reflection.dot(&view).max(0.0).powi(shininess);
+        }
+    }
+
+    let color = material.color;
+    color * illumination + color * emitted_illumination + material.specular_color * specular_illumination
+}
+
+/// Fraction, in [0, 1], of `samples` rays cast from `point` over the hemisphere around `normal`
+/// that hit an object beyond `epsilon`. Rays are cosine-weighted and spread along a golden angle spiral,
+/// so the same point always gets the same rays and the estimate is free of noise.
+fn ambient_occlusion(scene: &Scene, point: &Vector3<f64>, normal: &Vector3<f64>, samples: u32, epsilon: f64) -> f64 {
+    // Orthonormal basis around the normal, from whichever axis is least aligned with it.
+    let axis = if normal.x.abs() < 0.5 { Vector3::x() } else { Vector3::y() };
+    let tangent = normal.cross(&axis).normalize();
+    let bitangent = normal.cross(&tangent);
+
+    let golden_angle = PI * (3.0 - 5f64.sqrt());
+    let occluded = (0..samples).filter(|&i| {
+        let radius = ((i as f64 + 0.5) / samples as f64).sqrt();
+        let angle = i as f64 * golden_angle;
+        let direction = tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin())
+            + normal * (1.0 - radius * radius).sqrt();
+
+        let ray = Ray::new(*point, direction);
+        scene.occluded(&ray, epsilon, f64::INFINITY)
+    }).count();
+
+    occluded as f64 / samples as f64
+}
+
+/// Fraction of a spot light's intensity that is sent along `light_to_point`,
+/// for a spot light whose cone's axis is the normalized `direction`.
+fn spot_falloff(light_to_point: &Vector3<f64>, direction: &Vector3<f64>, cos_inner: f64, cos_outer: f64) -> f64 {
+    let cos_angle = light_to_point.normalize().dot(direction);
+
+    if cos_angle >= cos_inner {
+        return 1.0;
+    }
+    if cos_angle <= cos_outer {
+        return 0.0;
+    }
+
+    // smoothstep
+    let t = (cos_angle - cos_outer) / (cos_inner - cos_outer);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_core::{Background, Sphere};
+
+    fn sphere(center: Vector3<f64>, material: Material) -> Sphere {
+        Sphere{center, radius: 1.0, material: Some(material)}
+    }
+
+    fn scene(objects: Vec<Sphere>, lights: Vec<Light>) -> Scene {
+        Scene {
+            objects,
+            lights,
+            background: Background::Color(Color::new(0.0, 0.0, 0.0)),
+            default_material: Material::gray(),
+        }
+    }
+
+    fn ambient(intensity: f64) -> Light {
+        Light{kind: LightKind::Ambient, intensity}
+    }
+
+    /// The lighting of `point` on the sphere centered at the origin, seen from along its normal.
+    fn shade(scene: &Scene, point: Vector3<f64>, material: &Material, options: &ShadingOptions) -> Color {
+        let normal = point.normalize();
+        compute_lighting(scene, &scene.prepare_lights(), &point, &normal, &normal, material, options)
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_creases() {
+        // Two touching spheres, the crease is where they meet at x = 1.
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        let scene = scene(
+            vec![sphere(origin, Material::default()), sphere(Vector3::new(2.0, 0.0, 0.0), Material::default())],
+            vec![ambient(1.0)],
+        );
+        let options = ShadingOptions{ambient_occlusion_samples: 64, ..ShadingOptions::default()};
+
+        let open = shade(&scene, Vector3::new(-1.0, 0.0, 0.0), &Material::default(), &options);
+        let crease = shade(&scene, Vector3::new(1.0, 0.2, 0.0).normalize(), &Material::default(), &options);
+        assert_eq!(open, Color::new(1.0, 1.0, 1.0));
+        assert!(crease.r < 0.5 * open.r);
+
+        // Without occlusion both get the full ambient light.
+        let uniform = shade(&scene, Vector3::new(1.0, 0.2, 0.0).normalize(), &Material::default(), &ShadingOptions::default());
+        assert_eq!(uniform, open);
+    }
+
+    #[test]
+    fn ambient_coefficient_scales_the_ambient_light() {
+        let scene = scene(vec![], vec![ambient(0.8)]);
+        let full = Material::default();
+        let dim = Material{ambient: 0.25, ..Material::default()};
+
+        let point = Vector3::new(0.0, 0.0, 1.0);
+        assert_eq!(shade(&scene, point, &full, &ShadingOptions::default()), Color::new(0.8, 0.8, 0.8));
+        assert_eq!(shade(&scene, point, &dim, &ShadingOptions::default()), Color::new(0.2, 0.2, 0.2));
+    }
+
+    #[test]
+    fn highlight_takes_the_specular_color() {
+        let scene = scene(vec![], vec![Light{kind: LightKind::Directional(Vector3::z()), intensity: 1.0}]);
+        let matte = Material{color: Color::new(1.0, 0.0, 0.0), specular_color: Color::new(0.0, 1.0, 0.0), ..Material::default()};
+        let shiny = Material{shininess: Some(10), ..matte};
+
+        // Facing the light, the diffuse term is red and the highlight adds only green.
+        let point = Vector3::new(0.0, 0.0, 1.0);
+        assert_eq!(shade(&scene, point, &matte, &ShadingOptions::default()), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(shade(&scene, point, &shiny, &ShadingOptions::default()), Color::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn emissive_sphere_lights_its_neighbour() {
+        // A glowing sphere touching the matte one at x = 1, and no lights.
+        let glowing = Material{emission: Color::new(0.0, 0.0, 1.0), ..Material::default()};
+        let scene = scene(
+            vec![sphere(Vector3::new(0.0, 0.0, 0.0), Material::gray()), sphere(Vector3::new(2.0, 0.0, 0.0), glowing)],
+            vec![],
+        );
+        let facing = Vector3::new(1.0, 0.0, 0.0);
+        let away = Vector3::new(-1.0, 0.0, 0.0);
+
+        assert_eq!(shade(&scene, facing, &Material::gray(), &ShadingOptions::default()), Color::new(0.0, 0.0, 0.0));
+
+        let options = ShadingOptions{emissive_lighting: true, ..ShadingOptions::default()};
+        assert_eq!(shade(&scene, facing, &Material::gray(), &options), Color::new(0.0, 0.0, 0.5));
+        assert_eq!(shade(&scene, away, &Material::gray(), &options), Color::new(0.0, 0.0, 0.0));
+    }
+
+    /// A spot light's falloff towards a point `angle` radians away from its axis.
+    fn falloff_at(angle: f64, inner_angle: f64, outer_angle: f64) -> f64 {
+        let light_to_point = Vector3::new(angle.sin(), 0.0, angle.cos());
+        spot_falloff(&light_to_point, &Vector3::z(), inner_angle.cos(), outer_angle.cos())
+    }
+
+    #[test]
+    fn spot_falloff_across_the_penumbra() {
+        let (inner, outer): (f64, f64) = (0.2, 0.4);
+        let mid_penumbra = ((inner.cos() + outer.cos()) / 2.0).acos();
+
+        assert_eq!(falloff_at(0.0, inner, outer), 1.0);
+        assert_eq!(falloff_at(inner, inner, outer), 1.0);
+        assert!((falloff_at(mid_penumbra, inner, outer) - 0.5).abs() < 1e-9);
+        assert_eq!(falloff_at(outer, inner, outer), 0.0);
+        assert_eq!(falloff_at(0.5, inner, outer), 0.0);
+
+        // Smoothly decreasing in between.
+        let samples: Vec<f64> = (1..10).map(|i| falloff_at(inner + (outer - inner) * i as f64 / 10.0, inner, outer)).collect();
+        assert!(samples.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn spot_falloff_with_a_hard_edge() {
+        let angle = 0.3;
+
+        assert_eq!(falloff_at(0.29, angle, angle), 1.0);
+        assert_eq!(falloff_at(angle, angle, angle), 1.0);
+        assert_eq!(falloff_at(0.31, angle, angle), 0.0);
+    }
+}
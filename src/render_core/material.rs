@@ -0,0 +1,54 @@
+use super::Color;
+
+/// Describes how the surface of an object interacts with light.
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    /// Color of the diffuse and ambient light reflected by the surface.
+    pub color: Color,
+
+    /// Color of the specular highlights.
+    pub specular_color: Color,
+
+    /// Specular exponent of the surface, or `None` for a matte surface.
+    pub shininess: Option<i32>,
+
+    /// How much of the ambient light the surface receives.
+    pub ambient: f64,
+
+    /// Light emitted by the surface itself, independently of any lights.
+    pub emission: Color,
+
+    /// Makes the surface a holdout: it still hides the objects behind it,
+    /// but shows the background instead of being shaded.
+    /// Used to let real objects in a background plate occlude rendered ones.
+    pub holdout: bool,
+
+    /// Whether the surface is seen by rays from the camera.
+    /// Hidden surfaces are skipped by primary rays, showing whatever is behind them,
+    /// but still occlude the rays cast from other surfaces, like ambient occlusion rays.
+    pub visible_to_camera: bool,
+}
+
+impl Material {
+    /// A neutral gray matte material, used as the default material of scenes.
+    pub fn gray() -> Material {
+        Material {
+            color: Color::new(0.5, 0.5, 0.5),
+            ..Material::default()
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Material {
+        Material {
+            color: Color::new(1.0, 1.0, 1.0),
+            specular_color: Color::new(1.0, 1.0, 1.0),
+            shininess: None,
+            ambient: 1.0,
+            emission: Color::new(0.0, 0.0, 0.0),
+            holdout: false,
+            visible_to_camera: true,
+        }
+    }
+}
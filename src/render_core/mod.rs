@@ -0,0 +1,31 @@
+//! Geometry and shading math.
+//! Nothing in this module depends on the `image` crate or touches the filesystem:
+//! converting to and from images, and saving them, lives in the crate root.
+
+mod aabb;
+mod background;
+mod color;
+mod intersection;
+mod lighting;
+mod material;
+mod ray;
+mod sampler;
+mod scene;
+mod sky;
+mod texture;
+mod trace;
+mod validation;
+
+pub use aabb::Aabb;
+pub use background::{Background, ImageFit, ScreenPoint};
+pub use color::Color;
+pub use intersection::{intersect_ray_sphere, Hits};
+pub use lighting::{compute_lighting, PreparedLight, ShadingOptions};
+pub use material::Material;
+pub use ray::Ray;
+pub use sampler::Sampler;
+pub use scene::{Scene, Sphere, LightKind, Light};
+pub use sky::SkyModel;
+pub use texture::Texture;
+pub use trace::{closest_intersection, trace_albedo, trace_ray, trace_uv};
+pub use validation::SceneWarning;
@@ -0,0 +1,28 @@
+use nalgebra::{Vector3};
+
+/// A half-line starting at `origin` and extending along `direction`.
+/// Points on the ray are `origin + t * direction`, for t >= 0.
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vector3<f64>,
+    pub direction: Vector3<f64>,
+}
+
+impl Ray {
+    /// Creates a new Ray. `direction` does not need to be normalized.
+    pub fn new(origin: Vector3<f64>, direction: Vector3<f64>) -> Ray {
+        Ray{origin, direction}
+    }
+
+    /// The point of the ray at parameter t.
+    pub fn at(&self, t: f64) -> Vector3<f64> {
+        self.origin + self.direction.scale(t)
+    }
+
+    /// The parameter t at which the ray is `distance` away from its origin.
+    /// Epsilons should go through this, since directions are not normalized
+    /// and the same t is a different distance for every ray.
+    pub fn t_at_distance(&self, distance: f64) -> f64 {
+        distance / self.direction.norm()
+    }
+}
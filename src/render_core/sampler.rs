@@ -0,0 +1,79 @@
+/// Stateless source of random numbers for sampling.
+/// Every value is derived by hashing its pixel, sample index and dimension together with the seed,
+/// so any sample can be regenerated independently of the order in which samples are taken.
+#[derive(Clone, Copy, Debug)]
+pub struct Sampler {
+    pub seed: u64,
+}
+
+impl Sampler {
+    /// Creates a new Sampler with the specified global seed.
+    pub fn new(seed: u64) -> Sampler {
+        Sampler{seed}
+    }
+
+    /// A random value in [0, 1) for the given dimension of the sample `index`
+    /// of the pixel at position (x, y).
+    pub fn sample(&self, x: i32, y: i32, index: u32, dimension: u32) -> f64 {
+        let mut hash = splitmix64(self.seed);
+        for value in [x as u32, y as u32, index, dimension] {
+            hash = splitmix64(hash ^ value as u64);
+        }
+
+        // The 53 most significant bits fill the mantissa of a f64.
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Finalizer of the SplitMix64 generator, used as an integer hash.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn independent_samplers_agree_bit_for_bit() {
+        let (first, second) = (Sampler::new(42), Sampler::new(42));
+
+        for &(x, y) in &[(0, 0), (17, -3), (-512, 511), (-1, -1)] {
+            for index in 0..4 {
+                for dimension in 0..3 {
+                    let sample = first.sample(x, y, index, dimension);
+                    assert_eq!(sample.to_bits(), second.sample(x, y, index, dimension).to_bits());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_input_changes_the_sample() {
+        let sampler = Sampler::new(42);
+        let sample = sampler.sample(5, -7, 3, 1);
+
+        assert_ne!(Sampler::new(43).sample(5, -7, 3, 1), sample);
+        assert_ne!(sampler.sample(6, -7, 3, 1), sample);
+        assert_ne!(sampler.sample(5, 7, 3, 1), sample);
+        assert_ne!(sampler.sample(5, -7, 4, 1), sample);
+        assert_ne!(sampler.sample(5, -7, 3, 2), sample);
+    }
+
+    #[test]
+    fn samples_are_in_the_unit_interval() {
+        let sampler = Sampler::new(7);
+
+        for x in -16..16 {
+            for y in -16..16 {
+                for index in 0..8 {
+                    let sample = sampler.sample(x, y, index, 0);
+                    assert!((0.0..1.0).contains(&sample), "{}", sample);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use nalgebra::{Vector3};
+use std::f64::consts::PI;
+
+use super::{Aabb, Background, Material};
+
+/// Contains all objects and lights to be rendered.
+///
+/// Scenes use a left-handed coordinate system: +x points right, +y up and +z forward,
+/// away from the camera at the origin. Scenes authored in right-handed tools, which
+/// usually have +z pointing towards the viewer, can be converted with `convert_handedness`.
+pub struct Scene {
+    pub objects: Vec<Sphere>,
+    pub lights: Vec<Light>,
+    pub background: Background,
+
+    /// Material of the objects that don't specify one, usually `Material::gray()`.
+    pub default_material: Material,
+}
+
+impl Scene {
+    /// The material the sphere is rendered with: its own, or the scene's default if it has none.
+    pub fn material<'a>(&'a self, sphere: &'a Sphere) -> &'a Material {
+        sphere.material.as_ref().unwrap_or(&self.default_material)
+    }
+
+    /// The smallest axis-aligned box containing every object in the scene.
+    /// Empty scenes return `Aabb::empty()`.
+    pub fn bounds(&self) -> Aabb {
+        self.objects.iter().fold(Aabb::empty(), |bounds, sphere| bounds.union(&sphere.aabb()))
+    }
+
+    /// Uniformly scales the scene about the origin by `factor`,
+    /// adjusting sphere centers and radii and the positions of point and spot lights.
+    /// Directional and ambient lights are unaffected.
+    /// Panics if `factor` is not positive, since that would turn radii negative.
+    ///
+    /// Since the camera sits at the origin, the scaled scene renders the same as the original.
+    /// The exception is the near clip, which is not scaled with the scene: when shrinking
+    /// a scene, objects may end up closer to the camera than `RenderOptions::near_clip`
+    /// and get clipped, so scale it by the same factor. The same goes for
+    /// `ShadingOptions::surface_epsilon`, which offsets the rays cast from surfaces.
+    pub fn scaled(mut self, factor: f64) -> Scene {
+        assert!(factor > 0.0, "scene scale factor must be positive, got {}", factor);
+
+        for sphere in &mut self.objects {
+            sphere.center *= factor;
+            sphere.radius *= factor;
+        }
+
+        for light in &mut self.lights {
+            match &mut light.kind {
+                LightKind::Point(position) | LightKind::Spot{position, ..} => *position *= factor,
+                LightKind::Ambient | LightKind::Directional(_) => (),
+            }
+        }
+
+        self
+    }
+
+    /// Converts the scene between left and right-handed coordinates by mirroring it along z.
+    /// Sphere centers, light positions and directions, and the background are all mirrored.
+    /// Surface normals are computed from the geometry, so they follow along.
+    /// Converting twice returns to the original scene.
+    pub fn convert_handedness(&mut self) {
+        let mirror = |vector: &mut Vector3<f64>| vector.z = -vector.z;
+
+        for sphere in &mut self.objects {
+            mirror(&mut sphere.center);
+        }
+
+        for light in &mut self.lights {
+            match &mut light.kind {
+                LightKind::Ambient => (),
+                LightKind::Point(position) => mirror(position),
+                LightKind::Directional(direction) => mirror(direction),
+                LightKind::Spot{position, direction, ..} => {
+                    mirror(position);
+                    mirror(direction);
+                },
+            }
+        }
+
+        self.background.mirror_z();
+    }
+}
+
+/// A 3d spherical primitive.
+pub struct Sphere {
+    pub center: Vector3<f64>,
+    pub radius: f64,
+
+    /// Material of the sphere, or `None` to use the scene's default material.
+    pub material: Option<Material>,
+}
+
+impl Sphere {
+    /// The smallest axis-aligned box containing the sphere.
+    pub fn aabb(&self) -> Aabb {
+        let extent = Vector3::new(self.radius, self.radius, self.radius);
+
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    /// Texture coordinates (u, v), both in [0, 1], of a point on the surface of the sphere.
+    /// u goes around the vertical axis, starting and ending at the back of the sphere,
+    /// and v goes from the top to the bottom.
+    pub fn uv(&self, point: &Vector3<f64>) -> (f64, f64) {
+        let p = (point - self.center) / self.radius;
+
+        let u = 0.5 + p.x.atan2(-p.z) / (2.0 * PI);
+        let v = 0.5 - p.y.clamp(-1.0, 1.0).asin() / PI;
+        (u, v)
+    }
+}
+
+/// Type of the light.
+pub enum LightKind {
+    /// Ambient light that illuminates all points in the scene.
+    Ambient,
+    
+    /// A point of light located in a specified position.
+    /// Sends rays of light in all directions.
+    Point(Vector3<f64>),
+
+    /// Various light rays hitting the objects in the specified direction.
+    Directional(Vector3<f64>),
+
+    /// A point of light that only sends rays inside a cone around `direction`.
+    /// Points within `inner_angle` of the cone's axis receive the full intensity,
+    /// which fades smoothly to zero at `outer_angle`. Angles are in radians.
+    Spot {
+        position: Vector3<f64>,
+        direction: Vector3<f64>,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+}
+
+/// Light that illuminates the objects in the scene.
+pub struct Light {
+    pub kind: LightKind,
+    pub intensity: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_core::{Color, SkyModel, Texture};
+    use crate::{render_pixel, Canvas, RenderOptions};
+
+    fn scene(background: Background) -> Scene {
+        let spot = LightKind::Spot {
+            position: Vector3::new(1.0, 2.0, 3.0),
+            direction: Vector3::new(0.0, -1.0, 1.0),
+            inner_angle: 0.2,
+            outer_angle: 0.4,
+        };
+
+        Scene {
+            objects: vec![Sphere{center: Vector3::new(1.0, -1.0, 4.0), radius: 1.5, material: None}],
+            lights: vec![
+                LightKind::Ambient,
+                LightKind::Point(Vector3::new(2.0, 1.0, -3.0)),
+                LightKind::Directional(Vector3::new(1.0, 4.0, 4.0)),
+                spot,
+            ].into_iter().map(|kind| Light{kind, intensity: 0.5}).collect(),
+            background,
+            default_material: Material::gray(),
+        }
+    }
+
+    /// Every position and direction in the lights of the scene.
+    fn light_vectors(scene: &Scene) -> Vec<Vector3<f64>> {
+        scene.lights.iter().flat_map(|light| match light.kind {
+            LightKind::Ambient => vec![],
+            LightKind::Point(position) => vec![position],
+            LightKind::Directional(direction) => vec![direction],
+            LightKind::Spot{position, direction, ..} => vec![position, direction],
+        }).collect()
+    }
+
+    fn cube_map() -> Background {
+        // Each face has different pixels everywhere, so any flip or swap shows.
+        let face = |face: u32| Texture::from_fn(4, 4, |x, y| Color::new(face as f64, x as f64, y as f64));
+        Background::CubeMap([face(0), face(1), face(2), face(3), face(4), face(5)])
+    }
+
+    fn cube_map_faces(scene: &Scene) -> &[Texture; 6] {
+        match &scene.background {
+            Background::CubeMap(faces) => faces,
+            _ => unreachable!(),
+        }
+    }
+
+    fn sun_azimuth(scene: &Scene) -> f64 {
+        match &scene.background {
+            Background::Sky(sky) => sky.sun_azimuth,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn bounds_contain_every_sphere() {
+        let mut scene = scene(Background::Color(Color::new(0.0, 0.0, 0.0)));
+        scene.objects.push(Sphere{center: Vector3::new(-2.0, 0.0, 1.0), radius: 0.5, material: None});
+
+        let expected = scene.objects[0].aabb().union(&scene.objects[1].aabb());
+        assert_eq!(scene.bounds(), expected);
+        assert_eq!(expected, Aabb::new(Vector3::new(-2.5, -2.5, 0.5), Vector3::new(2.5, 0.5, 5.5)));
+
+        scene.objects.clear();
+        assert_eq!(scene.bounds(), Aabb::empty());
+    }
+
+    #[test]
+    fn scaled_scene_renders_the_same() {
+        let shiny = |color| Some(Material{color, shininess: Some(50), ..Material::default()});
+        let scene = || Scene {
+            objects: vec![
+                Sphere{center: Vector3::new(0.0, -1.0, 3.0), radius: 1.0, material: shiny(Color::new(1.0, 0.0, 0.0))},
+                Sphere{center: Vector3::new(1.5, 0.5, 4.0), radius: 1.0, material: shiny(Color::new(0.0, 0.0, 1.0))},
+            ],
+            lights: vec![
+                Light{kind: LightKind::Ambient, intensity: 0.2},
+                Light{kind: LightKind::Point(Vector3::new(2.0, 1.0, 0.0)), intensity: 0.6},
+            ],
+            background: Background::Color(Color::new(1.0, 1.0, 1.0)),
+            default_material: Material::gray(),
+        };
+        let original = scene();
+        let scaled = scene().scaled(2.5);
+        let options = RenderOptions::builder().near_clip(0.1).build().unwrap();
+        let scaled_options = RenderOptions::builder().near_clip(0.25).build().unwrap();
+
+        let canvas = Canvas::new(32, 32);
+        for x in -16..16 {
+            for y in -16..16 {
+                let expected = render_pixel(&canvas, &original, &options, x, y);
+                let color = render_pixel(&canvas, &scaled, &scaled_options, x, y);
+                let difference = (color.r - expected.r).abs() + (color.g - expected.g).abs() + (color.b - expected.b).abs();
+                assert!(difference < 1e-9, "pixel ({}, {}): {:?} != {:?}", x, y, color, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn scaling_by_a_negative_factor_panics() {
+        scene(Background::Color(Color::new(0.0, 0.0, 0.0))).scaled(-1.0);
+    }
+
+    #[test]
+    fn converting_handedness_twice_returns_the_original() {
+        let original = scene(cube_map());
+        let mut converted = scene(cube_map());
+
+        converted.convert_handedness();
+        assert_ne!(converted.objects[0].center, original.objects[0].center);
+        assert_ne!(light_vectors(&converted), light_vectors(&original));
+        assert_ne!(cube_map_faces(&converted), cube_map_faces(&original));
+
+        converted.convert_handedness();
+        assert_eq!(converted.objects[0].center, original.objects[0].center);
+        assert_eq!(converted.objects[0].radius, original.objects[0].radius);
+        assert_eq!(light_vectors(&converted), light_vectors(&original));
+        assert_eq!(cube_map_faces(&converted), cube_map_faces(&original));
+    }
+
+    #[test]
+    fn converting_handedness_twice_restores_the_sun() {
+        let original = scene(Background::Sky(SkyModel::new(0.5, 0.3, 3.0)));
+        let mut converted = scene(Background::Sky(SkyModel::new(0.5, 0.3, 3.0)));
+
+        converted.convert_handedness();
+        assert!((sun_azimuth(&converted) - sun_azimuth(&original)).abs() > 0.1);
+
+        converted.convert_handedness();
+        assert!((sun_azimuth(&converted) - sun_azimuth(&original)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,173 @@
+use nalgebra::{Vector3};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use super::{Color, Light, LightKind};
+
+/// Procedural daylight sky, following the analytic model by Preetham et al.
+/// The sky color depends on the sun position and on the turbidity of the atmosphere,
+/// going from a deep blue sky at midday to orange tones at sunset.
+#[derive(Clone, Copy, Debug)]
+pub struct SkyModel {
+    /// Angle of the sun above the horizon, in radians.
+    pub sun_elevation: f64,
+
+    /// Angle of the sun around the vertical axis, in radians, measured from +z towards +x.
+    pub sun_azimuth: f64,
+
+    /// Haziness of the atmosphere. 2 is a very clear sky and 10 a hazy one.
+    pub turbidity: f64,
+
+    /// Linear factor that converts the sky luminance, in kcd/m², into color units.
+    /// Unlike `RenderOptions::exposure`, it is not in stops.
+    pub luminance_scale: f64,
+
+    /// Angular radius, in radians, of the sun disk drawn in the sky.
+    /// Zero hides the disk.
+    pub sun_angular_radius: f64,
+
+    /// Color of the sun disk. It is usually brighter than 1, like the sun itself.
+    pub sun_color: Color,
+}
+
+impl SkyModel {
+    /// Creates a new SkyModel with the specified sun position and turbidity.
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64) -> SkyModel {
+        SkyModel {
+            sun_elevation,
+            sun_azimuth,
+            turbidity,
+            luminance_scale: 1.0 / 25.0,
+            sun_angular_radius: 0.00465,
+            sun_color: Color::new(10.0, 9.5, 8.5),
+        }
+    }
+
+    /// Unit vector pointing towards the sun.
+    pub fn sun_direction(&self) -> Vector3<f64> {
+        let (elevation, azimuth) = (self.sun_elevation, self.sun_azimuth);
+
+        Vector3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        )
+    }
+
+    /// A directional light coming from the sun, matching the sky.
+    pub fn sun_light(&self, intensity: f64) -> Light {
+        Light {
+            kind: LightKind::Directional(self.sun_direction()),
+            intensity,
+        }
+    }
+
+    /// The color of the sky seen along `direction`, or of the sun if it is within the sun disk.
+    /// Directions below the horizon see the sky at the horizon.
+    pub fn sample(&self, direction: &Vector3<f64>) -> Color {
+        let direction = direction.normalize();
+        let t = self.turbidity;
+
+        let sun_zenith = FRAC_PI_2 - self.sun_elevation;
+        let zenith = direction.y.max(0.001).acos();
+        let gamma = direction.dot(&self.sun_direction()).min(1.0).acos();
+
+        if gamma < self.sun_angular_radius {
+            return self.sun_color;
+        }
+
+        // Perez distribution coefficients for the luminance Y and the chromaticities x and y.
+        let coefficients_y = [
+            0.1787*t - 1.4630, -0.3554*t + 0.4275, -0.0227*t + 5.3251, 0.1206*t - 2.5771, -0.0670*t + 0.3703,
+        ];
+        let coefficients_x = [
+            -0.0193*t - 0.2592, -0.0665*t + 0.0008, -0.0004*t + 0.2125, -0.0641*t - 0.8989, -0.0033*t + 0.0452,
+        ];
+        let coefficients_chroma_y = [
+            -0.0167*t - 0.2608, -0.0950*t + 0.0092, -0.0079*t + 0.2102, -0.0441*t - 1.6537, -0.0109*t + 0.0529,
+        ];
+
+        let (zenith_x, zenith_y, zenith_luminance) = zenith_values(t, sun_zenith);
+        let relative = |coefficients: &[f64; 5]| {
+            perez(coefficients, zenith, gamma) / perez(coefficients, 0.0, sun_zenith)
+        };
+
+        let luminance = zenith_luminance * relative(&coefficients_y) * self.luminance_scale;
+        let x = zenith_x * relative(&coefficients_x);
+        let y = zenith_y * relative(&coefficients_chroma_y);
+
+        xyy_to_color(x, y, luminance)
+    }
+}
+
+/// Perez sky luminance distribution for a view at `zenith` from the zenith and `gamma` from the sun.
+fn perez(coefficients: &[f64; 5], zenith: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = *coefficients;
+
+    (1.0 + a * (b / zenith.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Chromaticities (x, y) and luminance, in kcd/m², of the sky at the zenith.
+fn zenith_values(t: f64, sun_zenith: f64) -> (f64, f64, f64) {
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+    let luminance = (4.0453*t - 4.9710) * chi.tan() - 0.2155*t + 2.4192;
+
+    let s = sun_zenith;
+    let (s2, s3) = (s * s, s * s * s);
+    let x = t*t * (0.00166*s3 - 0.00375*s2 + 0.00209*s)
+        + t * (-0.02903*s3 + 0.06377*s2 - 0.03202*s + 0.00394)
+        + (0.11693*s3 - 0.21196*s2 + 0.06052*s + 0.25886);
+    let y = t*t * (0.00275*s3 - 0.00610*s2 + 0.00317*s)
+        + t * (-0.04214*s3 + 0.08970*s2 - 0.04153*s + 0.00516)
+        + (0.15346*s3 - 0.26756*s2 + 0.06670*s + 0.26688);
+
+    (x, y, luminance)
+}
+
+/// Converts a color from CIE xyY to linear sRGB.
+fn xyy_to_color(x: f64, y: f64, luminance: f64) -> Color {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+
+    Color::new(
+        (3.2406*big_x - 1.5372*luminance - 0.4986*big_z).max(0.0),
+        (-0.9689*big_x + 1.8758*luminance + 0.0415*big_z).max(0.0),
+        (0.0557*big_x - 0.2040*luminance + 1.0570*big_z).max(0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_disk_ends_at_its_angular_radius() {
+        let sky = SkyModel::new(0.5, 0.0, 3.0);
+        assert_eq!(sky.sample(&sky.sun_direction()), sky.sun_color);
+
+        // Sun directions of skies with the sun slightly higher, inside and just outside the disk.
+        let raised = |angle: f64| SkyModel::new(sky.sun_elevation + angle, 0.0, 3.0).sun_direction();
+        assert_eq!(sky.sample(&raised(0.9 * sky.sun_angular_radius)), sky.sun_color);
+
+        let outside = sky.sample(&raised(1.1 * sky.sun_angular_radius));
+        assert_ne!(outside, sky.sun_color);
+        assert!(outside.luminance() < sky.sun_color.luminance(), "{:?}", outside);
+    }
+
+    #[test]
+    fn sunset_is_warmer_than_midday() {
+        let midday = SkyModel::new(1.3, 0.0, 3.0);
+        let sunset = SkyModel::new(0.05, 0.0, 3.0);
+
+        // Low in the sky, towards the sun but outside of its disk.
+        let towards_sun = Vector3::new(0.3, 0.2, 1.0);
+        let midday_color = midday.sample(&towards_sun);
+        let sunset_color = sunset.sample(&towards_sun);
+
+        assert!(midday_color.b > midday_color.r, "{:?}", midday_color);
+        assert!(sunset_color.r > sunset_color.g && sunset_color.g > sunset_color.b, "{:?}", sunset_color);
+
+        // The zenith darkens as the sun sets.
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        assert!(sunset.sample(&up).luminance() < midday.sample(&up).luminance());
+    }
+}
@@ -0,0 +1,70 @@
+use super::Color;
+
+/// A grid of colors used as an image by backgrounds, with rows from top to bottom.
+/// Images from the `image` crate are converted with `Texture::from`, in the crate root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Texture {
+    /// Creates a new Texture of the specified size, calling `pixel` with the position (x, y) of each pixel.
+    pub fn from_fn(width: u32, height: u32, mut pixel: impl FnMut(u32, u32) -> Color) -> Texture {
+        let pixels = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(x, y))
+            .collect();
+
+        Texture{width, height, pixels}
+    }
+
+    /// The width of this texture.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of this texture.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether the texture has no pixels, so it can't be sampled.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// The color of the pixel at position (x, y), from the top left corner.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Mirrors the texture left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.pixels.chunks_exact_mut(self.width.max(1) as usize) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the texture top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let width = self.width as usize;
+        for y in 0..self.height as usize / 2 {
+            let mirrored = self.height as usize - 1 - y;
+            for x in 0..width {
+                self.pixels.swap(y * width + x, mirrored * width + x);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl Texture {
+    /// A texture missing its last pixel, which panics when sampled.
+    /// Used to test how renders recover from panics.
+    pub(crate) fn missing_last_pixel(width: u32, height: u32) -> Texture {
+        let mut texture = Texture::from_fn(width, height, |_, _| Color::new(0.0, 1.0, 0.0));
+        texture.pixels.pop();
+        texture
+    }
+}
@@ -0,0 +1,308 @@
+use super::{Color, PreparedLight, Ray, Scene, ScreenPoint, ShadingOptions, Sphere, compute_lighting};
+
+/// Relative difference in t below which two hits are considered coincident.
+/// Coincident hits are resolved in favor of the first object in the scene, so touching
+/// surfaces don't flicker between frames because of tiny floating point differences.
+const TIE_EPSILON: f64 = 1e-9;
+
+/// Finds the closest object hit by the ray with t in (t_min, t_max), and the t of the hit.
+pub fn closest_intersection<'a>(scene: &'a Scene, ray: &Ray, t_min: f64, t_max: f64) -> Option<(&'a Sphere, f64)> {
+    closest_intersection_among(scene, ray, t_min, t_max, |_| true)
+}
+
+impl Scene {
+    /// Whether the ray hits any object with t in (t_min, t_max).
+    /// Cheaper than `closest_intersection` for shadow and occlusion rays,
+    /// since it stops at the first hit found instead of looking for the closest one.
+    pub fn occluded(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.objects.iter().any(|sphere| !sphere.hits(ray, t_min, t_max).is_empty())
+    }
+}
+
+/// Like `closest_intersection`, but skipping objects hidden from the camera.
+/// When the closest object is a holdout there is no hit, so the background shows through it
+/// while it still hides the objects behind it. Used for primary rays, in every render mode.
+fn closest_camera_intersection<'a>(scene: &'a Scene, ray: &Ray, t_min: f64, t_max: f64) -> Option<(&'a Sphere, f64)> {
+    closest_intersection_among(scene, ray, t_min, t_max, |sphere| scene.material(sphere).visible_to_camera)
+        .filter(|(sphere, _)| !scene.material(sphere).holdout)
+}
+
+/// Finds the closest object hit by the ray with t in (t_min, t_max), among the objects for which `include` is true.
+fn closest_intersection_among<'a>(
+    scene: &'a Scene, ray: &Ray, t_min: f64, t_max: f64,
+    include: impl Fn(&Sphere) -> bool
+) -> Option<(&'a Sphere, f64)> {
+    let mut closest_t = f64::INFINITY;
+    let mut closest_sphere = None;
+
+    for primitive in scene.objects.iter().filter(|sphere| include(sphere)) {
+        // Roots behind the origin are negative and rejected by t_min, so when the origin
+        // is inside a sphere only its far side, in front of the origin, is hit.
+        if let Some(&t) = primitive.hits(ray, t_min, t_max).first() {
+            if t < closest_t * (1.0 - TIE_EPSILON) {
+                closest_t = t;
+                closest_sphere = Some(primitive);
+            }
+        }
+    }
+
+    closest_sphere.map(|sphere| (sphere, closest_t))
+}
+
+/// Computes the color seen along the ray from the camera, considering only intersections with t in (t_min, t_max).
+/// `lights` are the scene's lights, prepared with `Scene::prepare_lights`,
+/// and `screen` is where the ray crosses the frame, for screen image backgrounds.
+pub fn trace_ray(
+    scene: &Scene, lights: &[PreparedLight], ray: &Ray, screen: &ScreenPoint,
+    t_min: f64, t_max: f64, options: &ShadingOptions
+) -> Color {
+    match closest_camera_intersection(scene, ray, t_min, t_max) {
+        Some((sphere, t)) => {
+            let point = ray.at(t);
+            let mut normal = (point - sphere.center).normalize();
+
+            // The ray started inside the sphere, shade its inner surface.
+            if normal.dot(&ray.direction) > 0.0 {
+                normal = -normal;
+            }
+
+            let material = scene.material(sphere);
+            compute_lighting(scene, lights, &point, &normal, &(-ray.direction), material, options) + material.emission
+        }
+        None => scene.background.sample(&ray.direction, screen)
+    }
+}
+
+/// Pass showing the unlit base color of the surface seen along the ray from the camera.
+pub fn trace_albedo(scene: &Scene, ray: &Ray, screen: &ScreenPoint, t_min: f64, t_max: f64) -> Color {
+    match closest_camera_intersection(scene, ray, t_min, t_max) {
+        Some((sphere, _)) => scene.material(sphere).color,
+        None => scene.background.sample(&ray.direction, screen)
+    }
+}
+
+/// Debug pass showing the texture coordinates of the surface seen along the ray from the camera,
+/// with u as red and v as green.
+pub fn trace_uv(scene: &Scene, ray: &Ray, screen: &ScreenPoint, t_min: f64, t_max: f64) -> Color {
+    match closest_camera_intersection(scene, ray, t_min, t_max) {
+        Some((sphere, t)) => {
+            let (u, v) = sphere.uv(&ray.at(t));
+            Color::new(u, v, 0.0)
+        }
+        None => scene.background.sample(&ray.direction, screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::render_core::{Background, Light, LightKind, Material, Sampler};
+    use crate::{render_pixel, Canvas, RenderOptions};
+
+    const BACKGROUND: Color = Color{r: 0.0, g: 0.0, b: 1.0};
+    const RED: Color = Color{r: 1.0, g: 0.0, b: 0.0};
+
+    fn sphere(z: f64, radius: f64, material: Material) -> Sphere {
+        Sphere{center: Vector3::new(0.0, 0.0, z), radius, material: Some(material)}
+    }
+
+    fn scene(objects: Vec<Sphere>) -> Scene {
+        Scene {
+            objects,
+            lights: vec![Light{kind: LightKind::Ambient, intensity: 1.0}],
+            background: Background::Color(BACKGROUND),
+            default_material: Material::gray(),
+        }
+    }
+
+    fn screen() -> ScreenPoint {
+        ScreenPoint{x: 0.5, y: 0.5, width: 1.0, height: 1.0}
+    }
+
+    fn forward() -> Ray {
+        Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0))
+    }
+
+    fn trace(scene: &Scene, ray: &Ray) -> Color {
+        trace_ray(scene, &scene.prepare_lights(), ray, &screen(), 0.001, f64::INFINITY, &ShadingOptions::default())
+    }
+
+    #[test]
+    fn holdout_shows_the_background_in_every_mode() {
+        let holdout = Material{color: RED, holdout: true, ..Material::default()};
+        let scene = scene(vec![sphere(3.0, 1.0, holdout), sphere(6.0, 1.0, Material::default())]);
+        let ray = forward();
+
+        assert_eq!(trace(&scene, &ray), BACKGROUND);
+        assert_eq!(trace_albedo(&scene, &ray, &screen(), 0.001, f64::INFINITY), BACKGROUND);
+        assert_eq!(trace_uv(&scene, &ray, &screen(), 0.001, f64::INFINITY), BACKGROUND);
+    }
+
+    #[test]
+    fn hidden_sphere_shows_what_is_behind_it() {
+        let hidden = Material{color: RED, visible_to_camera: false, ..Material::default()};
+
+        let in_front = scene(vec![sphere(3.0, 1.0, hidden), sphere(6.0, 1.0, Material::default())]);
+        assert_eq!(trace(&in_front, &forward()), Color::new(1.0, 1.0, 1.0));
+
+        let alone = scene(vec![sphere(3.0, 1.0, hidden)]);
+        assert_eq!(trace(&alone, &forward()), BACKGROUND);
+    }
+
+    #[test]
+    fn hidden_sphere_still_occludes() {
+        let hidden = Material{visible_to_camera: false, ..Material::default()};
+
+        let blocker = scene(vec![sphere(3.0, 1.0, hidden)]);
+        assert!(blocker.occluded(&forward(), 0.001, f64::INFINITY));
+
+        // A hidden sphere enclosing everything blocks all the ambient occlusion rays,
+        // while the camera still sees the sphere inside it.
+        let enclosed = scene(vec![sphere(0.0, 10.0, hidden), sphere(3.0, 1.0, Material::default())]);
+        let options = ShadingOptions{ambient_occlusion_samples: 16, ..ShadingOptions::default()};
+        let lights = enclosed.prepare_lights();
+        assert_eq!(trace(&enclosed, &forward()), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(trace_ray(&enclosed, &lights, &forward(), &screen(), 0.001, f64::INFINITY, &options), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn uv_pass_shows_the_texture_coordinates() {
+        let scene = scene(vec![sphere(3.0, 1.0, Material::default())]);
+        let uv = |ray: &Ray| trace_uv(&scene, ray, &screen(), 0.001, f64::INFINITY);
+
+        // The front of the sphere is halfway around it and halfway down.
+        assert_eq!(uv(&forward()), Color::new(0.5, 0.5, 0.0));
+
+        // Straight down onto its top.
+        let top = uv(&Ray::new(Vector3::new(0.0, 5.0, 3.0), Vector3::new(0.0, -1.0, 0.0)));
+        assert_eq!(top.g, 0.0);
+
+        let miss = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(uv(&miss), BACKGROUND);
+    }
+
+    #[test]
+    fn albedo_pass_ignores_the_lights() {
+        let orange = Material{color: Color::new(1.0, 0.5, 0.0), shininess: Some(10), ..Material::default()};
+        let albedo = |lights: Vec<Light>| {
+            let scene = Scene{lights, ..scene(vec![sphere(3.0, 1.0, orange)])};
+            trace_albedo(&scene, &forward(), &screen(), 0.001, f64::INFINITY)
+        };
+
+        assert_eq!(albedo(vec![]), orange.color);
+        assert_eq!(albedo(vec![Light{kind: LightKind::Point(Vector3::new(0.0, 0.0, 0.0)), intensity: 3.0}]), orange.color);
+        assert_eq!(albedo(vec![Light{kind: LightKind::Ambient, intensity: 0.1}]), orange.color);
+    }
+
+    #[test]
+    fn sphere_without_a_material_uses_the_default_material() {
+        let bare = || Sphere{center: Vector3::new(0.0, 0.0, 3.0), radius: 1.0, material: None};
+        let gray = scene(vec![bare()]);
+        assert_eq!(trace(&gray, &forward()), Material::gray().color);
+
+        let red = Scene{default_material: Material{color: RED, ..Material::default()}, ..scene(vec![bare()])};
+        assert_eq!(trace(&red, &forward()), RED);
+    }
+
+    /// The center pixel of a render with a 0.001 near clip, looking straight down +z.
+    fn center_pixel(scene: &Scene) -> Color {
+        let options = RenderOptions::builder().near_clip(0.001).build().unwrap();
+        render_pixel(&Canvas::new(2, 2), scene, &options, 0, 0)
+    }
+
+    #[test]
+    fn sphere_behind_the_camera_is_not_visible() {
+        let scene = scene(vec![sphere(-2.0, 1.0, Material{color: RED, ..Material::default()})]);
+
+        assert_eq!(center_pixel(&scene), BACKGROUND);
+    }
+
+    #[test]
+    fn sphere_straddling_the_camera_within_the_near_clip_is_not_visible() {
+        // Its far side is in front of the camera, but closer than the near clip.
+        let scene = scene(vec![sphere(-0.9995, 1.0, Material{color: RED, ..Material::default()})]);
+
+        assert_eq!(center_pixel(&scene), BACKGROUND);
+    }
+
+    #[test]
+    fn sphere_centered_at_the_camera_shows_its_far_side() {
+        let scene = scene(vec![sphere(0.0, 1.0, Material{color: RED, ..Material::default()})]);
+
+        assert_eq!(center_pixel(&scene), RED);
+        let (_, t) = closest_intersection(&scene, &forward(), 0.001, f64::INFINITY).unwrap();
+        assert_eq!(t, 1.0);
+    }
+
+    #[test]
+    fn sphere_just_in_front_of_the_camera_is_visible() {
+        let scene = scene(vec![sphere(1.0 + 0.002, 1.0, Material{color: RED, ..Material::default()})]);
+
+        assert_eq!(center_pixel(&scene), RED);
+        let (_, t) = closest_intersection(&scene, &forward(), 0.001, f64::INFINITY).unwrap();
+        assert!((t - 0.002).abs() < 1e-9);
+    }
+
+    #[test]
+    fn inner_surface_is_lit_from_inside() {
+        // A point light between the camera and the far side, both inside the sphere.
+        let scene = Scene {
+            objects: vec![sphere(0.0, 1.0, Material{color: RED, ..Material::default()})],
+            lights: vec![Light{kind: LightKind::Point(Vector3::new(0.0, 0.0, 0.5)), intensity: 1.0}],
+            background: Background::Color(BACKGROUND),
+            default_material: Material::gray(),
+        };
+
+        assert_eq!(trace(&scene, &forward()), RED);
+    }
+
+    #[test]
+    fn coincident_hits_resolve_to_the_first_sphere() {
+        // A ray from inside the first sphere reaches its far side right where the second begins.
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 3.0), Vector3::new(0.0, 0.0, 1.0));
+
+        for perturbation in [-1e-10, 0.0, 1e-10] {
+            let first = sphere(3.0, 1.0, Material::default());
+            let second = sphere(5.0 + perturbation, 1.0, Material::default());
+
+            let scene = scene(vec![first, second]);
+            let (hit, _) = closest_intersection(&scene, &ray, 0.001, f64::INFINITY).unwrap();
+            assert_eq!(hit.center.z, 3.0, "perturbation {}", perturbation);
+
+            // Swapping them swaps the winner.
+            let scene = Scene{objects: scene.objects.into_iter().rev().collect(), ..scene};
+            let (hit, _) = closest_intersection(&scene, &ray, 0.001, f64::INFINITY).unwrap();
+            assert_eq!(hit.center.z, 5.0 + perturbation, "perturbation {}", perturbation);
+        }
+    }
+
+    #[test]
+    fn occluded_agrees_with_closest_intersection() {
+        let sampler = Sampler::new(3);
+        let random = |i: u32, dimension: u32| sampler.sample(i as i32, 0, 0, dimension);
+        let objects = (0..20).map(|i| Sphere {
+            center: Vector3::new(random(i, 0) * 6.0 - 3.0, random(i, 1) * 6.0 - 3.0, random(i, 2) * 6.0),
+            radius: random(i, 3),
+            material: None,
+        }).collect();
+        let scene = scene(objects);
+
+        let mut occluded_rays = 0;
+        for i in 0..10000 {
+            let origin = Vector3::new(random(i, 4) * 8.0 - 4.0, random(i, 5) * 8.0 - 4.0, random(i, 6) * 8.0 - 1.0);
+            let direction = Vector3::new(random(i, 7) - 0.5, random(i, 8) - 0.5, random(i, 9) - 0.5);
+            let ray = Ray::new(origin, direction);
+            let t_max = random(i, 10) * 10.0;
+
+            let occluded = scene.occluded(&ray, 0.001, t_max);
+            assert_eq!(occluded, closest_intersection(&scene, &ray, 0.001, t_max).is_some(), "ray {}", i);
+            occluded_rays += occluded as u32;
+        }
+
+        // Both outcomes are covered.
+        assert!(occluded_rays > 100 && occluded_rays < 9900);
+    }
+
+}
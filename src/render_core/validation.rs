@@ -0,0 +1,251 @@
+use nalgebra::{Vector3};
+use std::fmt;
+
+use super::{Background, LightKind, Scene};
+
+/// A likely mistake in a scene, found by `Scene::validate`.
+/// Objects and lights are identified by their index in the scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneWarning {
+    /// The sphere's radius is zero or negative.
+    NonPositiveRadius(usize),
+
+    /// The sphere's center or radius is NaN or infinite.
+    NonFiniteSphere(usize),
+
+    /// The camera is inside the sphere, which hides the rest of the scene.
+    CameraInsideSphere(usize),
+
+    /// The light's intensity is zero, so it has no effect.
+    ZeroIntensityLight(usize),
+
+    /// The light's intensity, position, direction or spot angles are NaN or infinite.
+    NonFiniteLight(usize),
+
+    /// The light's direction is the zero vector.
+    ZeroLightDirection(usize),
+
+    /// The spot light's inner angle is larger than its outer angle.
+    InvertedSpotAngles(usize),
+
+    /// The scene has no lights, so every object renders black.
+    NoLights,
+
+    /// The background has an image with no pixels, which renders black.
+    EmptyBackgroundImage,
+}
+
+impl fmt::Display for SceneWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneWarning::NonPositiveRadius(i) => write!(f, "sphere {} has a non-positive radius", i),
+            SceneWarning::NonFiniteSphere(i) => write!(f, "sphere {} has a NaN or infinite center or radius", i),
+            SceneWarning::CameraInsideSphere(i) => write!(f, "the camera is inside sphere {}", i),
+            SceneWarning::ZeroIntensityLight(i) => write!(f, "light {} has zero intensity", i),
+            SceneWarning::NonFiniteLight(i) => write!(f, "light {} has a NaN or infinite value", i),
+            SceneWarning::ZeroLightDirection(i) => write!(f, "light {} has a zero direction", i),
+            SceneWarning::InvertedSpotAngles(i) => write!(f, "spot light {} has an inner angle larger than its outer angle", i),
+            SceneWarning::NoLights => write!(f, "the scene has no lights"),
+            SceneWarning::EmptyBackgroundImage => write!(f, "the background has an empty image"),
+        }
+    }
+}
+
+impl Scene {
+    /// Checks the scene for common mistakes before rendering it,
+    /// returning every problem found.
+    pub fn validate(&self) -> Result<(), Vec<SceneWarning>> {
+        let mut warnings = Vec::new();
+        let finite = |vector: &Vector3<f64>| vector.iter().all(|value| value.is_finite());
+
+        for (i, sphere) in self.objects.iter().enumerate() {
+            if !(finite(&sphere.center) && sphere.radius.is_finite()) {
+                warnings.push(SceneWarning::NonFiniteSphere(i));
+                continue;
+            }
+            if sphere.radius <= 0.0 {
+                warnings.push(SceneWarning::NonPositiveRadius(i));
+                continue;
+            }
+
+            // The camera is at the origin.
+            if sphere.center.norm() < sphere.radius {
+                warnings.push(SceneWarning::CameraInsideSphere(i));
+            }
+        }
+
+        for (i, light) in self.lights.iter().enumerate() {
+            let (position, direction, angles) = match &light.kind {
+                LightKind::Ambient => (None, None, None),
+                LightKind::Point(position) => (Some(position), None, None),
+                LightKind::Directional(direction) => (None, Some(direction), None),
+                LightKind::Spot{position, direction, inner_angle, outer_angle} => {
+                    (Some(position), Some(direction), Some((*inner_angle, *outer_angle)))
+                },
+            };
+
+            let finite_light = light.intensity.is_finite()
+                && position.iter().chain(direction.iter()).all(|vector| finite(vector))
+                && angles.iter().all(|(inner, outer)| inner.is_finite() && outer.is_finite());
+            if !finite_light {
+                warnings.push(SceneWarning::NonFiniteLight(i));
+                continue;
+            }
+
+            if light.intensity == 0.0 {
+                warnings.push(SceneWarning::ZeroIntensityLight(i));
+            }
+            if direction.is_some_and(|direction| direction.norm() == 0.0) {
+                warnings.push(SceneWarning::ZeroLightDirection(i));
+            }
+            if angles.is_some_and(|(inner, outer)| inner > outer) {
+                warnings.push(SceneWarning::InvertedSpotAngles(i));
+            }
+        }
+
+        if self.lights.is_empty() {
+            warnings.push(SceneWarning::NoLights);
+        }
+
+        let empty_image = match &self.background {
+            Background::CubeMap(faces) => faces.iter().any(|face| face.is_empty()),
+            Background::ScreenImage(image, _) => image.is_empty(),
+            Background::Color(_) | Background::Sky(_) => false,
+        };
+        if empty_image {
+            warnings.push(SceneWarning::EmptyBackgroundImage);
+        }
+
+        if warnings.is_empty() { Ok(()) } else { Err(warnings) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_core::{Color, ImageFit, Light, Material, Sphere, Texture};
+
+    fn sphere(center: Vector3<f64>, radius: f64) -> Sphere {
+        Sphere{center, radius, material: None}
+    }
+
+    fn light(kind: LightKind, intensity: f64) -> Light {
+        Light{kind, intensity}
+    }
+
+    fn spot(direction: Vector3<f64>, inner_angle: f64, outer_angle: f64) -> LightKind {
+        LightKind::Spot{position: Vector3::new(0.0, 0.0, 0.0), direction, inner_angle, outer_angle}
+    }
+
+    fn validate(objects: Vec<Sphere>, lights: Vec<Light>) -> Result<(), Vec<SceneWarning>> {
+        validate_with_background(objects, lights, Background::Color(Color::new(0.0, 0.0, 0.0)))
+    }
+
+    fn validate_with_background(objects: Vec<Sphere>, lights: Vec<Light>, background: Background) -> Result<(), Vec<SceneWarning>> {
+        let scene = Scene{objects, lights, background, default_material: Material::gray()};
+        scene.validate()
+    }
+
+    /// Validates a scene with a single valid sphere and the specified light.
+    fn validate_light(light: Light) -> Result<(), Vec<SceneWarning>> {
+        validate(vec![sphere(Vector3::new(0.0, 0.0, 5.0), 1.0)], vec![light])
+    }
+
+    /// Validates a scene with a single ambient light and the specified sphere.
+    fn validate_sphere(sphere: Sphere) -> Result<(), Vec<SceneWarning>> {
+        validate(vec![sphere], vec![light(LightKind::Ambient, 1.0)])
+    }
+
+    #[test]
+    fn valid_scene() {
+        assert_eq!(validate_light(light(spot(Vector3::z(), 0.1, 0.2), 1.0)), Ok(()));
+    }
+
+    #[test]
+    fn non_positive_radius() {
+        let center = Vector3::new(0.0, 0.0, 5.0);
+
+        assert_eq!(validate_sphere(sphere(center, 0.0)), Err(vec![SceneWarning::NonPositiveRadius(0)]));
+        assert_eq!(validate_sphere(sphere(center, -1.0)), Err(vec![SceneWarning::NonPositiveRadius(0)]));
+    }
+
+    #[test]
+    fn non_finite_sphere() {
+        let nan_center = sphere(Vector3::new(f64::NAN, 0.0, 5.0), 1.0);
+        let infinite_radius = sphere(Vector3::new(0.0, 0.0, 5.0), f64::INFINITY);
+
+        assert_eq!(validate_sphere(nan_center), Err(vec![SceneWarning::NonFiniteSphere(0)]));
+        assert_eq!(validate_sphere(infinite_radius), Err(vec![SceneWarning::NonFiniteSphere(0)]));
+    }
+
+    #[test]
+    fn camera_inside_sphere() {
+        let around_camera = sphere(Vector3::new(0.0, 0.0, 1.0), 2.0);
+
+        assert_eq!(validate_sphere(around_camera), Err(vec![SceneWarning::CameraInsideSphere(0)]));
+    }
+
+    #[test]
+    fn zero_intensity_light() {
+        assert_eq!(validate_light(light(LightKind::Ambient, 0.0)), Err(vec![SceneWarning::ZeroIntensityLight(0)]));
+    }
+
+    #[test]
+    fn non_finite_light_is_reported_once() {
+        let nan_everything = light(LightKind::Point(Vector3::new(f64::NAN, 0.0, 0.0)), f64::NAN);
+
+        assert_eq!(validate_light(nan_everything), Err(vec![SceneWarning::NonFiniteLight(0)]));
+    }
+
+    #[test]
+    fn non_finite_spot_angles() {
+        let nan_inner = light(spot(Vector3::z(), f64::NAN, 0.2), 1.0);
+        let nan_outer = light(spot(Vector3::z(), 0.1, f64::NAN), 1.0);
+
+        assert_eq!(validate_light(nan_inner), Err(vec![SceneWarning::NonFiniteLight(0)]));
+        assert_eq!(validate_light(nan_outer), Err(vec![SceneWarning::NonFiniteLight(0)]));
+    }
+
+    #[test]
+    fn zero_light_direction() {
+        let directional = light(LightKind::Directional(Vector3::zeros()), 1.0);
+
+        assert_eq!(validate_light(directional), Err(vec![SceneWarning::ZeroLightDirection(0)]));
+    }
+
+    #[test]
+    fn inverted_spot_angles() {
+        let inverted = light(spot(Vector3::z(), 0.3, 0.2), 1.0);
+
+        assert_eq!(validate_light(inverted), Err(vec![SceneWarning::InvertedSpotAngles(0)]));
+    }
+
+    #[test]
+    fn no_lights() {
+        let objects = vec![sphere(Vector3::new(0.0, 0.0, 5.0), 1.0)];
+
+        assert_eq!(validate(objects, vec![]), Err(vec![SceneWarning::NoLights]));
+    }
+
+    #[test]
+    fn empty_background_image() {
+        let texture = |size| Texture::from_fn(size, size, |_, _| Color::new(1.0, 1.0, 1.0));
+        let lights = || vec![light(LightKind::Ambient, 1.0)];
+        let cube_map = Background::CubeMap([texture(1), texture(1), texture(0), texture(1), texture(1), texture(1)]);
+        let screen_image = Background::ScreenImage(texture(0), ImageFit::Stretch);
+
+        assert_eq!(validate_with_background(vec![], lights(), cube_map), Err(vec![SceneWarning::EmptyBackgroundImage]));
+        assert_eq!(validate_with_background(vec![], lights(), screen_image), Err(vec![SceneWarning::EmptyBackgroundImage]));
+    }
+
+    #[test]
+    fn warnings_identify_their_object() {
+        let objects = vec![sphere(Vector3::new(0.0, 0.0, 5.0), 1.0), sphere(Vector3::new(0.0, 0.0, 5.0), 0.0)];
+        let lights = vec![light(LightKind::Ambient, 1.0), light(LightKind::Ambient, 0.0)];
+
+        assert_eq!(
+            validate(objects, lights),
+            Err(vec![SceneWarning::NonPositiveRadius(1), SceneWarning::ZeroIntensityLight(1)])
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use image::imageops::{self, FilterType};
+
+/// Resizes an 8-bit sRGB image to `width` x `height`, filtering in linear space.
+/// Resizing the encoded values directly, as `imageops::resize` does, averages them
+/// as if they were linear and darkens high contrast detail like edges and fine patterns.
+pub fn resize_linear(image: &RgbImage, width: u32, height: u32, filter: FilterType) -> RgbImage {
+    let linear: ImageBuffer<Rgb<f32>, Vec<f32>> = ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let Rgb(channels) = *image.get_pixel(x, y);
+        Rgb(channels.map(|channel| srgb_to_linear(channel as f32 / 255.0)))
+    });
+
+    let resized = imageops::resize(&linear, width, height, filter);
+
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let Rgb(channels) = *resized.get_pixel(x, y);
+        Rgb(channels.map(|channel| (linear_to_srgb(channel.clamp(0.0, 1.0)) * 255.0).round() as u8))
+    })
+}
+
+/// Decodes an sRGB encoded value in [0, 1] to linear intensity.
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear intensity in [0, 1] with the sRGB transfer function.
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Average linear intensity of the image, over all of its channels.
+    fn mean_intensity(image: &RgbImage) -> f32 {
+        let sum: f32 = image.pixels().flat_map(|pixel| pixel.0).map(|channel| srgb_to_linear(channel as f32 / 255.0)).sum();
+        sum / (image.width() * image.height() * 3) as f32
+    }
+
+    #[test]
+    fn halving_stripes_gives_half_intensity() {
+        let stripes = RgbImage::from_fn(64, 64, |x, _| if x % 2 == 0 { Rgb([255, 255, 255]) } else { Rgb([0, 0, 0]) });
+
+        // 188 encodes 50% intensity, the naive resize averages the encoded values to 128.
+        let resized = resize_linear(&stripes, 32, 32, FilterType::Triangle);
+        let naive = imageops::resize(&stripes, 32, 32, FilterType::Triangle);
+        assert_eq!(resized.get_pixel(10, 10), &Rgb([188, 188, 188]));
+        assert_eq!(naive.get_pixel(10, 10), &Rgb([128, 128, 128]));
+    }
+
+    #[test]
+    fn resizing_preserves_average_intensity() {
+        // A gradient with fine detail on top, which is what naive resizing darkens.
+        let image = RgbImage::from_fn(128, 128, |x, y| {
+            let value = if (x + y) % 2 == 0 { x * 2 } else { 255 - y };
+            Rgb([value as u8, (x + y) as u8, 255 - x as u8])
+        });
+
+        let original = mean_intensity(&image);
+        let linear_error = (mean_intensity(&resize_linear(&image, 32, 32, FilterType::Triangle)) - original).abs();
+        let naive_error = (mean_intensity(&imageops::resize(&image, 32, 32, FilterType::Triangle)) - original).abs();
+        assert!(linear_error < 0.01, "{}", linear_error);
+        assert!(linear_error < naive_error / 4.0, "{} vs {}", linear_error, naive_error);
+    }
+}